@@ -0,0 +1,544 @@
+//! Bracketing root finders, which keep a root enclosed in an interval over which f() changes sign
+//! and therefore cannot diverge.
+
+use crate::roots::Counted;
+use crate::{NumlError, Real, RootReport, Termination};
+
+/// The result of validating a bracket: either one of the endpoints is already a root, or the
+/// endpoints are ordered so that a < b and f(a) and f(b) have opposite signs.
+pub(crate) enum Bracket<T> {
+    Root { x: T },
+    Interval { a: T, fa: T, b: T, fb: T },
+}
+
+/// Evaluates f() at both endpoints and checks that they bracket a root.
+pub(crate) fn check_bracket<T: Real, F: FnMut(T) -> T>(f: &mut Counted<F>, a: T, b: T) -> Result<Bracket<T>, NumlError> {
+    if !a.is_finite() {
+        return Err(NumlError::InvalidInput { name: "a", value: a.value() });
+    }
+    if !b.is_finite() {
+        return Err(NumlError::InvalidInput { name: "b", value: b.value() });
+    }
+    let (a, b) = if a <= b { (a, b) } else { (b, a) };
+
+    let fa = f.eval(a);
+    check_value(a, fa)?;
+    if fa == T::ZERO {
+        return Ok(Bracket::Root { x: a });
+    }
+    let fb = f.eval(b);
+    check_value(b, fb)?;
+    if fb == T::ZERO {
+        return Ok(Bracket::Root { x: b });
+    }
+
+    if (fa < T::ZERO) == (fb < T::ZERO) {
+        return Err(NumlError::InvalidBracket { a: a.value(), b: b.value(), fa: fa.value(), fb: fb.value() });
+    }
+    Ok(Bracket::Interval { a, fa, b, fb })
+}
+
+/// Returns a NumlError::NonFiniteValue if fx is NaN. Infinite values still have a sign, so they
+/// are allowed inside a bracket.
+pub(crate) fn check_value<T: Real>(x: T, fx: T) -> Result<(), NumlError> {
+    if fx.is_nan() {
+        return Err(NumlError::NonFiniteValue { x: x.value(), fx: fx.value(), derivative: f64::NAN });
+    }
+    Ok(())
+}
+
+/// Checks that a bracket width tolerance is a nonnegative number.
+pub(crate) fn check_tol<T: Real>(tol: T) -> Result<(), NumlError> {
+    if tol.is_nan() || tol < T::ZERO {
+        return Err(NumlError::InvalidInput { name: "tol", value: tol.value() });
+    }
+    Ok(())
+}
+
+/// Returns the midpoint of [a, b] without overflowing, even for a bracket as wide as
+/// [-T::MAX, T::MAX].
+pub(crate) fn midpoint<T: Real>(a: T, b: T) -> T {
+    let half = T::from_f64(0.5);
+    let m = a + half*(b - a);
+    if m.is_finite() { m } else { half*a + half*b }
+}
+
+/// Finds a root of f() in the interval [a, b] by bisection.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - a: T
+/// - b: T
+/// - tol: T
+///
+/// f(a) and f(b) must have opposite signs, in which case a continuous f() has a root between them.
+/// Every iteration evaluates f() at the midpoint of the bracket and keeps the half over which f()
+/// changes sign, so the bracket halves with every evaluation and the iteration cannot diverge or
+/// cycle, whatever f() looks like. a and b may be passed in either order.
+///
+/// The iteration stops when f() is exactly zero at an endpoint or midpoint, when the bracket is
+/// no wider than tol, or when the midpoint rounds to one of the endpoints, which means that the
+/// endpoints are adjacent floating point numbers and the bracket cannot be split any further.
+/// Because of the last rule, tol can be zero to get the root to full precision; this takes at
+/// most a few dozen iterations for brackets which don't straddle zero, and at most about
+/// 2100 iterations for f64 otherwise. The returned root is the endpoint of the final bracket with
+/// the smaller |f|.
+///
+/// Errors:
+/// - NumlError::InvalidInput if a or b is not finite, or if tol is negative or NaN
+/// - NumlError::InvalidBracket if f(a) and f(b) are nonzero and have the same sign
+/// - NumlError::NonFiniteValue if f() evaluates to NaN (infinities are allowed, as they still
+///   have a sign)
+pub fn bisect<T: Real>(f: impl FnMut(T) -> T, a: T, b: T, tol: T) -> Result<RootReport<T>, NumlError> {
+    check_tol(tol)?;
+    let mut f = Counted::new(f);
+    let (mut a, mut fa, mut b, mut fb) = match check_bracket(&mut f, a, b)? {
+        Bracket::Root { x } => {
+            return Ok(RootReport { root: x, iterations: 0, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+        }
+        Bracket::Interval { a, fa, b, fb } => (a, fa, b, fb),
+    };
+
+    let mut iterations = 0;
+    loop {
+        let m = midpoint(a, b);
+        if b - a <= tol || m == a || m == b {
+            let (root, fx) = if fa.abs() <= fb.abs() { (a, fa) } else { (b, fb) };
+            return Ok(RootReport { root, iterations, evaluations: f.evaluations, residual: fx.abs(), termination: Termination::BracketTolerance });
+        }
+
+        let fm = f.eval(m);
+        check_value(m, fm)?;
+        iterations += 1;
+        if fm == T::ZERO {
+            return Ok(RootReport { root: m, iterations, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+        }
+        if (fm < T::ZERO) == (fa < T::ZERO) {
+            a = m;
+            fa = fm;
+        } else {
+            b = m;
+            fb = fm;
+        }
+    }
+}
+
+/// Finds a root of f() in the interval [a, b] with Brent's method.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - a: T
+/// - b: T
+/// - tol: T
+///
+/// Like bisect(), this needs f(a) and f(b) to have opposite signs and keeps the root bracketed at
+/// all times, but instead of always halving the bracket it takes a secant or inverse quadratic
+/// interpolation step whenever that step stays well inside the bracket and shrinks it fast
+/// enough, and falls back to bisection otherwise. On smooth functions this converges
+/// superlinearly, usually needing far fewer evaluations than bisection, while never needing more
+/// than about the square of the number bisection would.
+///
+/// The iteration stops when f() is exactly zero at the current best estimate, or when the bracket
+/// around it is no wider than 4*EPS*|root| + tol. tol can therefore be zero to get the root to
+/// nearly full precision. The returned root is the end of the final bracket with the smaller |f|.
+/// Brackets as wide as [-T::MAX, T::MAX] are supported, as for bisect().
+///
+/// The errors are the same as those of bisect(), plus a NumlError::NoConvergence if the number of
+/// iterations exceeds the square of the number of bisection steps that would shrink the bracket
+/// to the smallest positive number. This is only a safeguard, as the bound above keeps the
+/// iteration well within it.
+pub fn brent<T: Real>(f: impl FnMut(T) -> T, a: T, b: T, tol: T) -> Result<RootReport<T>, NumlError> {
+    check_tol(tol)?;
+    let mut f = Counted::new(f);
+    let (mut a, mut fa, mut b, mut fb) = match check_bracket(&mut f, a, b)? {
+        Bracket::Root { x } => {
+            return Ok(RootReport { root: x, iterations: 0, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+        }
+        Bracket::Interval { a, fa, b, fb } => (a, fa, b, fb),
+    };
+
+    let two = T::from_f64(2.0);
+    let three = T::from_f64(3.0);
+    let half = T::from_f64(0.5);
+
+    // Iteration cap, from the number of bisection steps that would shrink the bracket to the
+    // smallest positive number. The width is halved rather than subtracted from to avoid
+    // overflow for huge brackets.
+    let mut n_half = 1usize;
+    let mut width = half*b - half*a;
+    while width > T::EPSILON*T::MIN_POSITIVE {
+        width = half*width;
+        n_half += 1;
+    }
+    let max_iterations = n_half.saturating_mul(n_half);
+
+    // b is the best estimate of the root, c is the other end of the bracket and a is the previous
+    // value of b. d is the current step and e the one before it. Steps spanning a huge bracket
+    // can overflow to infinity, which only makes the interpolation below fall back to bisection.
+    let (mut c, mut fc) = (b, fb);
+    let mut d = b - a;
+    let mut e = d;
+    let mut iterations = 0;
+    loop {
+        if (fb < T::ZERO) == (fc < T::ZERO) {
+            c = a;
+            fc = fa;
+            d = b - a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            a = b;
+            b = c;
+            c = a;
+            fa = fb;
+            fb = fc;
+            fc = fa;
+        }
+
+        let tol1 = two*T::EPSILON*b.abs() + half*tol;
+        let xm = half*c - half*b;
+        if fb == T::ZERO || xm.abs() <= tol1 {
+            let termination = if fb == T::ZERO { Termination::ExactRoot } else { Termination::BracketTolerance };
+            return Ok(RootReport { root: b, iterations, evaluations: f.evaluations, residual: fb.abs(), termination });
+        }
+        if iterations >= max_iterations {
+            let slope = (fc - fb)/(c - b);
+            return Err(NumlError::NoConvergence { iterations, x: b.value(), fx: fb.value(), derivative: slope.value() });
+        }
+
+        if e.abs() >= tol1 && fa.abs() > fb.abs() {
+            let s = fb/fa;
+            let (mut p, mut q) = if a == c {
+                // Secant step.
+                (two*xm*s, T::ONE - s)
+            } else {
+                // Inverse quadratic interpolation through a, b and c.
+                let q = fa/fc;
+                let r = fb/fc;
+                (s*(two*xm*q*(q - r) - (b - a)*(r - T::ONE)), (q - T::ONE)*(r - T::ONE)*(s - T::ONE))
+            };
+            if p > T::ZERO {
+                q = -q;
+            } else {
+                p = -p;
+            }
+            // Accept the interpolation only if it falls inside the bracket and the step is less
+            // than half of the step before last, so that the bracket keeps shrinking.
+            if two*p < T::min(three*xm*q - (tol1*q).abs(), (e*q).abs()) {
+                e = d;
+                d = p/q;
+            } else {
+                d = xm;
+                e = d;
+            }
+        } else {
+            d = xm;
+            e = d;
+        }
+
+        a = b;
+        fa = fb;
+        let step = if d.abs() > tol1 { d } else { tol1.copysign(xm) };
+        // An interpolation step computed from overflowed values can land outside the bracket, in
+        // which case the bracket is bisected instead.
+        let next = b + step;
+        b = if next >= b.min(c) && next <= b.max(c) { next } else { b + xm };
+        fb = f.eval(b);
+        check_value(b, fb)?;
+        iterations += 1;
+    }
+}
+
+/// Tuning parameters of itp().
+///
+/// Each iteration starts from the regula falsi point, moves it towards the midpoint of the bracket
+/// by delta = k1/(b0 - a0)*(b - a)^k2, where [a0, b0] is the initial bracket and [a, b] the
+/// current one, and then projects it back into a shrinking neighbourhood of the midpoint, whose
+/// size is chosen so that the method needs at most n0 more iterations than bisection would.
+///
+/// k1 must be positive, k2 must lie in [1, 1 + golden ratio) and larger values of n0 give the
+/// interpolation more freedom at the cost of a worse guaranteed iteration count. The defaults
+/// k1 = 0.2, k2 = 2 and n0 = 1 are the values recommended by Oliveira and Takahashi.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItpOptions<T = f64> {
+    pub k1: T,
+    pub k2: T,
+    pub n0: usize,
+}
+
+impl<T: Real> Default for ItpOptions<T> {
+    fn default() -> Self {
+        ItpOptions {
+            k1: T::from_f64(0.2),
+            k2: T::from_f64(2.0),
+            n0: 1,
+        }
+    }
+}
+
+/// Finds a root of f() in the interval [a, b] with the ITP (interpolate, truncate, project)
+/// method of Oliveira and Takahashi.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - a: T
+/// - b: T
+/// - tol: T
+/// - options: ItpOptions<T>
+///
+/// Like bisect(), this needs f(a) and f(b) to have opposite signs and keeps the root bracketed at
+/// all times. It never needs more than n0 iterations more than bisection to shrink the bracket to
+/// a width of tol, whatever f() looks like, and converges superlinearly on smooth functions. See
+/// ItpOptions for the tuning parameters.
+///
+/// The iteration stops when f() is exactly zero at an evaluated point, when the bracket is no
+/// wider than tol, or when its endpoints are adjacent floating point numbers. The guarantee above
+/// needs a positive tolerance, so tol is raised to at least 2*EPS*max(|a|, |b|) for the initial
+/// a and b; pass an explicit tol if the root may be much closer to zero than the endpoints are.
+/// The returned root is the endpoint of the final bracket with the smaller |f|.
+///
+/// The errors are the same as those of bisect(), plus a NumlError::InvalidInput if the options
+/// are out of range.
+pub fn itp<T: Real>(f: impl FnMut(T) -> T, a: T, b: T, tol: T, options: ItpOptions<T>) -> Result<RootReport<T>, NumlError> {
+    check_tol(tol)?;
+    if !(options.k1 > T::ZERO && options.k1.is_finite()) {
+        return Err(NumlError::InvalidInput { name: "k1", value: options.k1.value() });
+    }
+    if !(options.k2 >= T::ONE && options.k2 < T::from_f64(2.618033988749895)) {
+        return Err(NumlError::InvalidInput { name: "k2", value: options.k2.value() });
+    }
+
+    let mut f = Counted::new(f);
+    let (mut a, mut fa, mut b, mut fb) = match check_bracket(&mut f, a, b)? {
+        Bracket::Root { x } => {
+            return Ok(RootReport { root: x, iterations: 0, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+        }
+        Bracket::Interval { a, fa, b, fb } => (a, fa, b, fb),
+    };
+
+    let two = T::from_f64(2.0);
+    let half = T::from_f64(0.5);
+    let eps = T::max(half*tol, T::EPSILON*T::max(a.abs(), b.abs()));
+    let k1 = options.k1/(b - a);
+    // Number of bisection steps needed to shrink the bracket to 2*eps. The width is halved
+    // rather than subtracted from to avoid overflow for huge brackets.
+    let mut n_half = 0;
+    let mut width = half*b - half*a;
+    while width > eps {
+        width = half*width;
+        n_half += 1;
+    }
+    let n_max = n_half + options.n0;
+
+    let mut iterations = 0;
+    loop {
+        let x_half = midpoint(a, b);
+        if b - a <= two*eps || x_half == a || x_half == b {
+            let (root, fx) = if fa.abs() <= fb.abs() { (a, fa) } else { (b, fb) };
+            return Ok(RootReport { root, iterations, evaluations: f.evaluations, residual: fx.abs(), termination: Termination::BracketTolerance });
+        }
+
+        // Interpolate: the regula falsi point.
+        let x_f = (fb*a - fa*b)/(fb - fa);
+        // Truncate: move towards the midpoint, which gives superlinear convergence.
+        let sigma = (x_half - x_f).signum();
+        let delta = k1*(b - a).powf(options.k2.value());
+        let x_t = if x_f.is_finite() && delta <= (x_half - x_f).abs() { x_f + sigma*delta } else { x_half };
+        // Project: stay close enough to the midpoint to keep bisection's worst case.
+        let r = eps*two.powi(n_max.saturating_sub(iterations).min(i32::MAX as usize) as i32) - (half*b - half*a);
+        let mut x = if (x_t - x_half).abs() <= r { x_t } else { x_half - sigma*r };
+        if !(x > a && x < b) {
+            x = x_half;
+        }
+
+        let fx = f.eval(x);
+        check_value(x, fx)?;
+        iterations += 1;
+        if fx == T::ZERO {
+            return Ok(RootReport { root: x, iterations, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+        }
+        if (fx < T::ZERO) == (fa < T::ZERO) {
+            a = x;
+            fa = fx;
+        } else {
+            b = x;
+            fb = fx;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_cubic(x: f64) -> f64 {
+        (x*x*x) + (2.0*x*x) - 0.4
+    }
+
+    #[test]
+    fn test_bisect() {
+        let report = bisect(sample_cubic, 0.0, 1.0, 0.0).unwrap();
+        assert!(report.root > 0.4 && report.root < 0.41);
+        assert_eq!(report.termination, Termination::BracketTolerance);
+        assert_eq!(report.evaluations, report.iterations + 2);
+        // The final bracket consists of adjacent floating point numbers.
+        let other = if sample_cubic(report.root) < 0.0 { report.root.next_up() } else { report.root.next_down() };
+        assert!(sample_cubic(report.root)*sample_cubic(other) <= 0.0);
+        assert!(report.iterations <= 54);
+    }
+
+    #[test]
+    fn test_bisect_tolerance() {
+        let report = bisect(sample_cubic, 1.0, 0.0, 1e-6).unwrap();
+        assert!(report.root > 0.4 && report.root < 0.41);
+        assert_eq!(report.iterations, 20);
+        assert_eq!(report.residual, sample_cubic(report.root).abs());
+    }
+
+    #[test]
+    fn test_bisect_exact_roots() {
+        let report = bisect(|x: f64| x - 1.0, 1.0, 3.0, 0.0).unwrap();
+        assert_eq!((report.root, report.iterations, report.termination), (1.0, 0, Termination::ExactRoot));
+        let report = bisect(|x: f64| x - 2.0, 1.0, 3.0, 0.0).unwrap();
+        assert_eq!((report.root, report.iterations, report.termination), (2.0, 1, Termination::ExactRoot));
+    }
+
+    #[test]
+    fn test_bisect_wide_bracket() {
+        let report = bisect(|x: f64| x - 1e-300, -f64::MAX, f64::MAX, 0.0).unwrap();
+        assert!((report.root - 1e-300).abs() <= 1e-300*f64::EPSILON);
+        let report = bisect(|x: f32| x.powi(3) - 2.0, 0.0, 2.0, 0.0).unwrap();
+        assert!((report.root - 2.0f32.cbrt()).abs() <= 2.0*f32::EPSILON);
+    }
+
+    #[test]
+    fn test_bisect_errors() {
+        let result = bisect(sample_cubic, 1.0, 2.0, 0.0);
+        assert!(matches!(result, Err(NumlError::InvalidBracket { a: 1.0, b: 2.0, .. })));
+        let result = bisect(|x: f64| if x > 0.5 { f64::NAN } else { x - 0.7 }, 0.0, 1.0, 0.0);
+        assert!(matches!(result, Err(NumlError::NonFiniteValue { x: 1.0, .. })));
+        let result = bisect(|x: f64| (x - 0.3).sqrt() - 0.1, 1.0, 0.0, 0.0);
+        assert!(matches!(result, Err(NumlError::NonFiniteValue { x: 0.0, .. })));
+        let result = bisect(sample_cubic, 0.0, f64::NAN, 0.0);
+        assert!(matches!(result, Err(NumlError::InvalidInput { name: "b", .. })));
+        let result = bisect(sample_cubic, 0.0, 1.0, -1.0);
+        assert!(matches!(result, Err(NumlError::InvalidInput { name: "tol", .. })));
+    }
+
+    #[test]
+    fn test_bisect_discontinuous() {
+        // A sign change without a root is located just as well as a root.
+        let report = bisect(|x: f64| if x < 0.25 { -1.0 } else { 1.0 }, 0.0, 1.0, 0.0).unwrap();
+        assert!((report.root - 0.25).abs() <= f64::EPSILON);
+    }
+
+    #[test]
+    fn test_brent() {
+        let report = brent(sample_cubic, 0.0, 1.0, 0.0).unwrap();
+        assert!(sample_cubic(report.root).abs() < 1e-15);
+        assert_eq!(report.evaluations, report.iterations + 2);
+        let bisection = bisect(sample_cubic, 0.0, 1.0, 0.0).unwrap();
+        assert!((report.root - bisection.root).abs() <= 4.0*f64::EPSILON);
+        assert!(report.evaluations < bisection.evaluations/3);
+    }
+
+    #[test]
+    fn test_brent_against_nqn() {
+        // nqn() from the same starting point as the other tests, iterated until the step is
+        // negligible, spends three evaluations per iteration.
+        let mut nqn_evaluations = 0;
+        let mut guess = 1.0;
+        loop {
+            let next = crate::nqn_mut(|x: f64| { nqn_evaluations += 1; sample_cubic(x) }, guess, 0.5).unwrap();
+            if (next - guess).abs() <= 4.0*f64::EPSILON*next.abs() {
+                break;
+            }
+            guess = next;
+        }
+
+        let report = brent(sample_cubic, 0.0, 1.0, 0.0).unwrap();
+        assert!((report.root - guess).abs() <= 4.0*f64::EPSILON);
+        assert!(report.evaluations < nqn_evaluations);
+    }
+
+    #[test]
+    fn test_brent_tolerance() {
+        let report = brent(sample_cubic, 1.0, 0.0, 1e-4).unwrap();
+        assert!(report.root > 0.4 && report.root < 0.41);
+        assert_eq!(report.termination, Termination::BracketTolerance);
+        assert!(report.evaluations <= brent(sample_cubic, 1.0, 0.0, 0.0).unwrap().evaluations);
+    }
+
+    #[test]
+    fn test_brent_hard_functions() {
+        // A discontinuity and a root of high multiplicity, where interpolation is useless and the
+        // bisection fallback has to do the work.
+        let report = brent(|x: f64| if x < 0.25 { -1.0 } else { 1.0 }, 0.0, 1.0, 0.0).unwrap();
+        assert!((report.root - 0.25).abs() <= 2.0*f64::EPSILON);
+        let report = brent(|x: f64| (x - 0.3).powi(9), 0.0, 1.0, 1e-10).unwrap();
+        assert!((report.root - 0.3).abs() <= 1e-10);
+        assert!(report.evaluations < 200);
+    }
+
+    #[test]
+    fn test_brent_errors() {
+        assert!(matches!(brent(sample_cubic, 1.0, 2.0, 0.0), Err(NumlError::InvalidBracket { .. })));
+        assert!(matches!(brent(|x: f64| x.ln(), -1.0, 2.0, 0.0), Err(NumlError::NonFiniteValue { x: -1.0, .. })));
+    }
+
+    #[test]
+    fn test_brent_wide_bracket() {
+        let report = brent(|x: f64| x - 1e-300, -f64::MAX, f64::MAX, 0.0).unwrap();
+        assert!((report.root - 1e-300).abs() <= 4.0*1e-300*f64::EPSILON);
+        let report = brent(|x: f64| x - 1.0, -1e308, 1e308, 0.0).unwrap();
+        assert!((report.root - 1.0).abs() <= 4.0*f64::EPSILON);
+        let report = brent(|x: f32| x.powi(3) - 2.0, -f32::MAX, f32::MAX, 0.0).unwrap();
+        assert!((report.root - 2.0f32.cbrt()).abs() <= 4.0*f32::EPSILON);
+    }
+
+    #[test]
+    fn test_brent_f32() {
+        let report = brent(|x: f32| x*x - 2.0, 0.0, 2.0, 0.0).unwrap();
+        assert!((report.root - f32::sqrt(2.0)).abs() <= 2.0*f32::EPSILON);
+    }
+
+    #[test]
+    fn test_itp() {
+        let report = itp(sample_cubic, 0.0, 1.0, 1e-12, ItpOptions::default()).unwrap();
+        assert!(sample_cubic(report.root).abs() < 1e-11);
+        let bisection = bisect(sample_cubic, 0.0, 1.0, 1e-12).unwrap();
+        assert!((report.root - bisection.root).abs() < 1e-12);
+        assert!(report.evaluations < bisection.evaluations/2);
+    }
+
+    #[test]
+    fn test_itp_worst_case() {
+        // On a discontinuous function interpolation is no help, but ITP still needs at most n0
+        // iterations more than bisection.
+        let step = |x: f64| if x < 1.0/3.0 { -1.0 } else { 1.0 };
+        for n0 in [0, 1, 5] {
+            let options = ItpOptions { n0, ..Default::default() };
+            let report = itp(step, 0.0, 1.0, 1e-9, options).unwrap();
+            let bisection = bisect(step, 0.0, 1.0, 1e-9).unwrap();
+            assert!((report.root - 1.0/3.0).abs() <= 1e-9);
+            assert!(report.evaluations <= bisection.evaluations + n0);
+        }
+    }
+
+    #[test]
+    fn test_itp_full_precision() {
+        let report = itp(|x: f64| x.exp() - 2.0, 0.0, 2.0, 0.0, ItpOptions::default()).unwrap();
+        assert!((report.root - f64::ln(2.0)).abs() <= 4.0*f64::EPSILON);
+        let report = itp(|x: f32| x*x - 2.0, 2.0, 0.0, 0.0, ItpOptions::default()).unwrap();
+        assert!((report.root - f32::sqrt(2.0)).abs() <= 4.0*f32::EPSILON);
+    }
+
+    #[test]
+    fn test_itp_errors() {
+        assert!(matches!(itp(sample_cubic, 1.0, 2.0, 0.0, ItpOptions::default()), Err(NumlError::InvalidBracket { .. })));
+        let options = ItpOptions { k1: 0.0, ..Default::default() };
+        assert!(matches!(itp(sample_cubic, 0.0, 1.0, 0.0, options), Err(NumlError::InvalidInput { name: "k1", .. })));
+        let options = ItpOptions { k2: 3.0, ..Default::default() };
+        assert!(matches!(itp(sample_cubic, 0.0, 1.0, 0.0, options), Err(NumlError::InvalidInput { name: "k2", .. })));
+    }
+}
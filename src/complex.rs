@@ -0,0 +1,387 @@
+//! A lightweight complex number type.
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::Real;
+
+/// A complex number re + im*i.
+///
+/// The parts can be of any type implementing Real, and default to f64. Only the operations needed
+/// by numl are provided: arithmetic (with other complex numbers and with plain real values) and
+/// the common elementary functions. The elementary functions are written so that they stay
+/// accurate when the imaginary part is tiny compared to the real part, which is what
+/// derivative_complex_step() relies on.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Complex<T = f64> {
+    pub re: T,
+    pub im: T,
+}
+
+impl<T: Real> Complex<T> {
+    /// The imaginary unit.
+    pub const I: Self = Complex { re: T::ZERO, im: T::ONE };
+
+    /// Creates the complex number re + im*i.
+    pub const fn new(re: T, im: T) -> Self {
+        Complex { re, im }
+    }
+
+    /// Creates the complex number with modulus r and argument theta.
+    pub fn from_polar(r: T, theta: T) -> Self {
+        Complex::new(r*theta.cos(), r*theta.sin())
+    }
+
+    /// Complex conjugate.
+    pub fn conj(self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+
+    /// Modulus |z|, computed without undue overflow or underflow.
+    pub fn abs(self) -> T {
+        self.re.hypot(self.im)
+    }
+
+    /// Squared modulus |z|^2.
+    pub fn norm_sqr(self) -> T {
+        self.re*self.re + self.im*self.im
+    }
+
+    /// Argument of z in (-pi, pi].
+    pub fn arg(self) -> T {
+        self.im.atan2(self.re)
+    }
+
+    /// Returns true if both parts are finite.
+    pub fn is_finite(self) -> bool {
+        self.re.is_finite() && self.im.is_finite()
+    }
+
+    /// Returns true if either part is NaN.
+    pub fn is_nan(self) -> bool {
+        self.re.is_nan() || self.im.is_nan()
+    }
+
+    /// 1/z.
+    pub fn recip(self) -> Self {
+        Complex::new(T::ONE, T::ZERO)/self
+    }
+
+    /// Exponential function.
+    pub fn exp(self) -> Self {
+        Complex::from_polar(self.re.exp(), self.im)
+    }
+
+    /// Principal natural logarithm, with imaginary part in (-pi, pi].
+    pub fn ln(self) -> Self {
+        Complex::new(self.abs().ln(), self.arg())
+    }
+
+    /// Principal square root, with nonnegative real part.
+    pub fn sqrt(self) -> Self {
+        if self.re == T::ZERO && self.im == T::ZERO {
+            return Complex::new(T::ZERO, self.im);
+        }
+        // Only ever add quantities of the same sign, to avoid cancellation for small imaginary
+        // parts.
+        let two = T::from_f64(2.0);
+        let t = ((self.abs() + self.re.abs())/two).sqrt();
+        if self.re >= T::ZERO {
+            Complex::new(t, self.im/(two*t))
+        } else {
+            Complex::new(self.im.abs()/(two*t), t.copysign(self.im))
+        }
+    }
+
+    /// Raises z to an integer power by repeated squaring.
+    pub fn powi(self, n: i32) -> Self {
+        let mut base = if n < 0 { self.recip() } else { self };
+        let mut exponent = n.unsigned_abs();
+        let mut result = Complex::new(T::ONE, T::ZERO);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result *= base;
+            }
+            base *= base;
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Raises z to a real power, using the principal branch of the logarithm.
+    pub fn powf(self, p: T) -> Self {
+        if self.re == T::ZERO && self.im == T::ZERO {
+            return if p == T::ZERO { Complex::new(T::ONE, T::ZERO) } else { Complex::new(T::ZERO, T::ZERO) };
+        }
+        (self.ln()*p).exp()
+    }
+
+    /// Raises z to a complex power, using the principal branch of the logarithm.
+    pub fn powc(self, p: Self) -> Self {
+        if self.re == T::ZERO && self.im == T::ZERO {
+            return if p.re == T::ZERO && p.im == T::ZERO { Complex::new(T::ONE, T::ZERO) } else { Complex::new(T::ZERO, T::ZERO) };
+        }
+        (self.ln()*p).exp()
+    }
+
+    /// Sine.
+    pub fn sin(self) -> Self {
+        Complex::new(self.re.sin()*self.im.cosh(), self.re.cos()*self.im.sinh())
+    }
+
+    /// Cosine.
+    pub fn cos(self) -> Self {
+        Complex::new(self.re.cos()*self.im.cosh(), -self.re.sin()*self.im.sinh())
+    }
+
+    /// Tangent.
+    pub fn tan(self) -> Self {
+        self.sin()/self.cos()
+    }
+
+    /// Hyperbolic sine.
+    pub fn sinh(self) -> Self {
+        Complex::new(self.re.sinh()*self.im.cos(), self.re.cosh()*self.im.sin())
+    }
+
+    /// Hyperbolic cosine.
+    pub fn cosh(self) -> Self {
+        Complex::new(self.re.cosh()*self.im.cos(), self.re.sinh()*self.im.sin())
+    }
+
+    /// Hyperbolic tangent.
+    pub fn tanh(self) -> Self {
+        self.sinh()/self.cosh()
+    }
+}
+
+impl<T: Real> From<T> for Complex<T> {
+    fn from(re: T) -> Self {
+        Complex::new(re, T::ZERO)
+    }
+}
+
+impl<T: Real> fmt::Display for Complex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.im.is_sign_negative() {
+            write!(f, "{}-{}i", self.re, -self.im)
+        } else {
+            write!(f, "{}+{}i", self.re, self.im)
+        }
+    }
+}
+
+impl<T: Real> Neg for Complex<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+impl<T: Real> Add for Complex<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl<T: Real> Sub for Complex<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl<T: Real> Mul for Complex<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Complex::new(self.re*rhs.re - self.im*rhs.im, self.re*rhs.im + self.im*rhs.re)
+    }
+}
+
+impl<T: Real> Div for Complex<T> {
+    type Output = Self;
+
+    /// Uses Smith's algorithm, which avoids overflow and underflow in the intermediate results.
+    fn div(self, rhs: Self) -> Self {
+        if rhs.re.abs() >= rhs.im.abs() {
+            let ratio = rhs.im/rhs.re;
+            let denominator = rhs.re + rhs.im*ratio;
+            Complex::new((self.re + self.im*ratio)/denominator, (self.im - self.re*ratio)/denominator)
+        } else {
+            let ratio = rhs.re/rhs.im;
+            let denominator = rhs.re*ratio + rhs.im;
+            Complex::new((self.re*ratio + self.im)/denominator, (self.im*ratio - self.re)/denominator)
+        }
+    }
+}
+
+impl<T: Real> Add<T> for Complex<T> {
+    type Output = Self;
+
+    fn add(self, rhs: T) -> Self {
+        Complex::new(self.re + rhs, self.im)
+    }
+}
+
+impl<T: Real> Sub<T> for Complex<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: T) -> Self {
+        Complex::new(self.re - rhs, self.im)
+    }
+}
+
+impl<T: Real> Mul<T> for Complex<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        Complex::new(self.re*rhs, self.im*rhs)
+    }
+}
+
+impl<T: Real> Div<T> for Complex<T> {
+    type Output = Self;
+
+    fn div(self, rhs: T) -> Self {
+        Complex::new(self.re/rhs, self.im/rhs)
+    }
+}
+
+/// Implements arithmetic with a real number on the left and a complex number on the right, which
+/// the orphan rules only allow for concrete types.
+macro_rules! impl_real_lhs_ops {
+    ($($t:ty),*) => {$(
+        impl Add<Complex<$t>> for $t {
+            type Output = Complex<$t>;
+
+            fn add(self, rhs: Complex<$t>) -> Complex<$t> {
+                rhs + self
+            }
+        }
+
+        impl Sub<Complex<$t>> for $t {
+            type Output = Complex<$t>;
+
+            fn sub(self, rhs: Complex<$t>) -> Complex<$t> {
+                Complex::new(self - rhs.re, -rhs.im)
+            }
+        }
+
+        impl Mul<Complex<$t>> for $t {
+            type Output = Complex<$t>;
+
+            fn mul(self, rhs: Complex<$t>) -> Complex<$t> {
+                rhs*self
+            }
+        }
+
+        impl Div<Complex<$t>> for $t {
+            type Output = Complex<$t>;
+
+            fn div(self, rhs: Complex<$t>) -> Complex<$t> {
+                Complex::from(self)/rhs
+            }
+        }
+    )*};
+}
+
+impl_real_lhs_ops!(f32, f64);
+
+impl<T: Real> AddAssign for Complex<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Real> SubAssign for Complex<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T: Real> MulAssign for Complex<T> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self*rhs;
+    }
+}
+
+impl<T: Real> DivAssign for Complex<T> {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self/rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn close(a: Complex, b: Complex, tol: f64) -> bool {
+        (a - b).abs() <= tol
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -4.0);
+        assert_eq!(a + b, Complex::new(4.0, -2.0));
+        assert_eq!(a - b, Complex::new(-2.0, 6.0));
+        assert_eq!(a*b, Complex::new(11.0, 2.0));
+        assert!(close(a/b, Complex::new(-0.2, 0.4), 1e-16));
+        assert!(close((a/b)*b, a, 1e-15));
+        assert_eq!(2.0 - a, Complex::new(1.0, -2.0));
+        assert_eq!(Complex::I*Complex::I, Complex::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_division_avoids_overflow() {
+        let big = Complex::new(1e300, 1e300);
+        assert!(close(big/big, Complex::new(1.0, 0.0), 1e-15));
+    }
+
+    #[test]
+    fn test_elementary_functions() {
+        assert!(close((Complex::I*PI).exp(), Complex::new(-1.0, 0.0), 1e-15));
+        assert!(close(Complex::new(-1.0, 0.0).ln(), Complex::new(0.0, PI), 1e-15));
+        assert!(close(Complex::new(-4.0, 0.0).sqrt(), Complex::new(0.0, 2.0), 1e-15));
+        assert!(close(Complex::new(3.0, 4.0).sqrt(), Complex::new(2.0, 1.0), 1e-15));
+        assert!(close(Complex::new(1.0, 1.0).powi(4), Complex::new(-4.0, 0.0), 1e-14));
+        assert!(close(Complex::new(2.0, 0.0).powi(-2), Complex::new(0.25, 0.0), 1e-16));
+        assert!(close(Complex::new(-8.0, 0.0).powf(1.0/3.0), Complex::from_polar(2.0, PI/3.0), 1e-14));
+
+        let z = Complex::new(0.3, -0.7);
+        let one = z.sin()*z.sin() + z.cos()*z.cos();
+        assert!(close(one, Complex::new(1.0, 0.0), 1e-15));
+        assert!(close(z.tan(), z.sin()/z.cos(), 1e-15));
+        assert!(close(z.cosh()*z.cosh() - z.sinh()*z.sinh(), Complex::new(1.0, 0.0), 1e-15));
+        assert!(close(z.ln().exp(), z, 1e-15));
+    }
+
+    #[test]
+    fn test_small_imaginary_parts_are_accurate() {
+        // The imaginary part must carry the derivative even when it is far below EPS.
+        let z = Complex::new(2.0, 1e-30);
+        assert_eq!(z.sqrt().im, 1e-30/(2.0*f64::sqrt(2.0)));
+        assert_eq!(z.ln().im, 0.5e-30);
+        assert_eq!(Complex::new(-2.0, 1e-30).sqrt().re, 1e-30/(2.0*f64::sqrt(2.0)));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Complex::new(1.5, -2.0).to_string(), "1.5-2i");
+        assert_eq!(Complex::new(1.5, 2.0).to_string(), "1.5+2i");
+    }
+
+    #[test]
+    fn test_f32() {
+        let z = Complex::new(3.0f32, 4.0);
+        assert_eq!(z.abs(), 5.0);
+        assert!((z.sqrt() - Complex::new(2.0, 1.0)).abs() <= 4.0*f32::EPSILON);
+        assert_eq!(2.0f32*z, Complex::new(6.0, 8.0));
+    }
+}
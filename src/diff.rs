@@ -0,0 +1,641 @@
+//! Numerical differentiation routines beyond the single central difference of derivative().
+
+use std::ops::RangeInclusive;
+
+use crate::{Complex, Dual, NumlError, Real};
+
+/// A derivative together with an estimate of its absolute error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DerivativeEstimate<T = f64> {
+    /// The computed derivative.
+    pub derivative: T,
+
+    /// Estimated absolute error of the derivative.
+    pub error: T,
+}
+
+/// Numerically calculates the derivative of the given function at the specified point using
+/// Ridders' method, returning the derivative together with an estimate of its error.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - typ: T
+///
+/// f() is the function whose derivative is being computed, x is the point at which that
+/// derivative is computed, and typ is the typical size of x. Please see the documentation of
+/// NumlError::TypError for more information on the typical value parameter.
+///
+/// Central differences are computed for a sequence of shrinking step sizes, starting at
+/// 0.1*max(|x|, |typ|) and dividing by 1.4 every time, and are combined in a Neville tableau
+/// which extrapolates the result to a step size of zero. The extrapolation stops as soon as the
+/// error starts growing again due to round-off, and the entry of the tableau with the smallest
+/// estimated error is returned.
+///
+/// For smooth functions this usually gives 10 or more correct digits, compared to the roughly
+/// 2/3 of the available precision that derivative() achieves, at the cost of up to 20
+/// evaluations of f() instead of 2.
+pub fn derivative_richardson<T: Real>(mut f: impl FnMut(T) -> T, x: T, typ: T) -> Result<DerivativeEstimate<T>, NumlError> {
+    const NTAB: usize = 10;
+    let con = T::from_f64(1.4);
+    let con2 = con*con;
+    let safe = T::from_f64(2.0);
+    let two = T::from_f64(2.0);
+
+    check_typ(typ)?;
+
+    let mut central = |h: T| {
+        // Make sure that x+h and x-h are exactly h away from x.
+        let h = (x + h) - x;
+        (f(x+h) - f(x-h))/(two*h)
+    };
+
+    let mut h = T::from_f64(0.1)*x.abs().max(typ.abs());
+    let mut tableau = [[T::ZERO; NTAB]; NTAB];
+    tableau[0][0] = central(h);
+
+    let mut best = DerivativeEstimate { derivative: tableau[0][0], error: T::INFINITY };
+    for i in 1..NTAB {
+        h /= con;
+        tableau[0][i] = central(h);
+
+        let mut factor = con2;
+        for j in 1..=i {
+            tableau[j][i] = (tableau[j-1][i]*factor - tableau[j-1][i-1])/(factor - T::ONE);
+            factor *= con2;
+
+            let error = T::max(
+                (tableau[j][i] - tableau[j-1][i]).abs(),
+                (tableau[j][i] - tableau[j-1][i-1]).abs(),
+            );
+            if error <= best.error {
+                best = DerivativeEstimate { derivative: tableau[j][i], error };
+            }
+        }
+
+        // Higher orders are getting worse, so round-off has taken over.
+        if (tableau[i][i] - tableau[i-1][i-1]).abs() >= safe*best.error {
+            break;
+        }
+    }
+
+    Ok(best)
+}
+
+/// Numerically calculates the nth derivative of the given function at the specified point using a
+/// second-order accurate central difference stencil.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - n: usize
+/// - typ: T
+///
+/// f() is the function whose derivative is being computed, x is the point at which that
+/// derivative is computed, n is the order of the derivative, and typ is the typical size of x.
+/// Please see the documentation of NumlError::TypError for more information on the typical value
+/// parameter.
+///
+/// The step size is EPS^(1/(n+2))*max(|x|, |typ|), which balances the O(h^2) truncation error of
+/// the stencil against the O(EPS/h^n) round-off error. For n = 1 this is the stencil used by
+/// derivative(), with essentially the same step size. Higher derivatives lose accuracy quickly:
+/// expect roughly 2/(n+2) of the available precision. Use nth_derivative_with_accuracy() for a
+/// wider stencil.
+///
+/// n = 0 simply returns f(x).
+pub fn nth_derivative<T: Real>(f: impl FnMut(T) -> T, x: T, n: usize, typ: T) -> Result<T, NumlError> {
+    nth_derivative_with_accuracy(f, x, n, 2, typ)
+}
+
+/// Numerically calculates the nth derivative of the given function at the specified point using a
+/// central difference stencil of the requested order of accuracy.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - n: usize
+/// - accuracy: usize
+/// - typ: T
+///
+/// This behaves like nth_derivative(), but uses a central stencil whose truncation error is
+/// O(h^accuracy). Central stencils always have an even order of accuracy, so accuracy is rounded
+/// up to the next even number, with a minimum of 2. The step size is
+/// EPS^(1/(n+accuracy))*max(|x|, |typ|).
+///
+/// The stencil uses 2*ceil(n/2) - 1 + accuracy points, and f() is
+/// evaluated once at each of them. For even n the centre point x is one of them.
+pub fn nth_derivative_with_accuracy<T: Real>(mut f: impl FnMut(T) -> T, x: T, n: usize, accuracy: usize, typ: T) -> Result<T, NumlError> {
+
+    check_typ(typ)?;
+
+    if n == 0 {
+        return Ok(f(x));
+    }
+
+    let accuracy = usize::max(2, accuracy + accuracy%2);
+    let half_width = n.div_ceil(2) - 1 + accuracy/2;
+    let offsets: Vec<T> = (-(half_width as i64)..=half_width as i64).map(|k| T::from_f64(k as f64)).collect();
+
+    stencil_derivative(f, x, &offsets, n, typ)
+}
+
+/// Numerically calculates the derivative of the given function at the specified point using a
+/// forward difference, which only evaluates f() at x and to the right of x.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - accuracy: usize
+/// - typ: T
+///
+/// f() is evaluated at x, x+h, ..., x+accuracy*h, and the truncation error of the stencil is
+/// O(h^accuracy). The usual choices are accuracy = 1, the two-point forward difference with
+/// h = sqrt(EPS)*max(|x|, |typ|), and accuracy = 2, the three-point forward difference with
+/// h = cbrt(EPS)*max(|x|, |typ|), which is about as accurate as derivative(). accuracy = 0 is
+/// treated as 1.
+///
+/// This is useful for functions that are only defined on [x, infinity) near x, such as
+/// f64::sqrt at a point close to zero. See derivative_in_domain() to pick the direction
+/// automatically.
+pub fn derivative_forward<T: Real>(f: impl FnMut(T) -> T, x: T, accuracy: usize, typ: T) -> Result<T, NumlError> {
+    let offsets: Vec<T> = (0..=usize::max(1, accuracy)).map(|k| T::from_f64(k as f64)).collect();
+    stencil_derivative(f, x, &offsets, 1, typ)
+}
+
+/// Numerically calculates the derivative of the given function at the specified point using a
+/// backward difference, which only evaluates f() at x and to the left of x.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - accuracy: usize
+/// - typ: T
+///
+/// This is the mirror image of derivative_forward(): f() is evaluated at x, x-h, ...,
+/// x-accuracy*h, with the same step size rule.
+pub fn derivative_backward<T: Real>(f: impl FnMut(T) -> T, x: T, accuracy: usize, typ: T) -> Result<T, NumlError> {
+    let offsets: Vec<T> = (0..=usize::max(1, accuracy)).map(|k| T::from_f64(-(k as f64))).collect();
+    stencil_derivative(f, x, &offsets, 1, typ)
+}
+
+/// Numerically calculates the derivative of the given function at the specified point without
+/// ever evaluating f() outside of the given domain.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - typ: T
+/// - domain: RangeInclusive<T>
+///
+/// The step size h = cbrt(EPS)*max(|x|, |typ|) is the same as that of derivative(). If
+/// [x-h, x+h] lies inside the domain, the central difference of derivative() is used. Otherwise
+/// the second-order forward difference on [x, x+2h] or backward difference on [x-2h, x] is used,
+/// whichever fits, so the result is accurate to O(h^2) in every case. Infinite bounds are allowed,
+/// for example 0.0..=f64::INFINITY for f64::ln.
+///
+/// A NumlError::DomainError is returned if x lies outside of the domain, or if the domain is too
+/// narrow to fit any of the three stencils. In the latter case, passing a smaller typ will shrink
+/// the step size.
+pub fn derivative_in_domain<T: Real>(f: impl FnMut(T) -> T, x: T, typ: T, domain: RangeInclusive<T>) -> Result<T, NumlError> {
+
+    check_typ(typ)?;
+
+    let (lower, upper) = (*domain.start(), *domain.end());
+    if !(lower <= x && x <= upper) {
+        return Err(NumlError::DomainError);
+    }
+
+    let h = step_size(x, typ, 1, 2);
+    let two = T::from_f64(2.0);
+    let offsets = if lower <= x - h && x + h <= upper {
+        [1.0, -1.0].as_slice()
+    } else if x + two*h <= upper {
+        [0.0, 1.0, 2.0].as_slice()
+    } else if lower <= x - two*h {
+        [0.0, -1.0, -2.0].as_slice()
+    } else {
+        return Err(NumlError::DomainError);
+    };
+    let offsets: Vec<T> = offsets.iter().map(|offset| T::from_f64(*offset)).collect();
+
+    stencil_derivative(f, x, &offsets, 1, typ)
+}
+
+/// Numerically calculates the nth derivative of the given function at the specified point using
+/// an arbitrary finite difference stencil.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - offsets: &[T]
+/// - n: usize
+/// - typ: T
+///
+/// f() is evaluated at x + offsets[i]*h for every offset whose weight (as computed by
+/// fd_weights()) is nonzero, in the order the offsets are given. The offsets do not need to be
+/// symmetric, evenly spaced or sorted, so one-sided and off-centre stencils can be used, for
+/// example [0, 1, 2] for a second-order forward difference of the first derivative.
+///
+/// The order of accuracy p of the stencil is determined from its weights, and the step size is
+/// EPS^(1/(n+p))*max(|x|, |typ|), which balances the O(h^p) truncation error against the
+/// O(EPS/h^n) round-off error. Please see the documentation of NumlError::TypError for more
+/// information on the typical value parameter.
+///
+/// A NumlError::StencilError is returned if the offsets cannot be used to approximate the nth
+/// derivative; see fd_weights() for details.
+pub fn stencil_derivative<T: Real>(mut f: impl FnMut(T) -> T, x: T, offsets: &[T], n: usize, typ: T) -> Result<T, NumlError> {
+
+    check_typ(typ)?;
+
+    let weights = fd_weights(T::ZERO, offsets, n)?;
+
+    // The weights are exact for polynomials of degree < offsets.len(), and symmetric stencils are
+    // exact for one degree more.
+    let mut accuracy = offsets.len() - n;
+    let mut moment = T::ZERO;
+    let mut scale = T::ZERO;
+    for (w, s) in weights.iter().zip(offsets) {
+        let term = *w*s.powi(offsets.len() as i32);
+        moment += term;
+        scale += term.abs();
+    }
+    if moment.abs() <= T::from_f64(64.0)*T::EPSILON*scale {
+        accuracy += 1;
+    }
+
+    let h = step_size(x, typ, n, accuracy);
+
+    let mut sum = T::ZERO;
+    for (offset, weight) in offsets.iter().zip(&weights) {
+        if *weight != T::ZERO {
+            sum += *weight*f(x + *offset*h);
+        }
+    }
+
+    Ok(sum/h.powi(n as i32))
+}
+
+/// Calculates the derivative of a real-analytic function at the specified point using the
+/// complex-step method.
+///
+/// Inputs:
+/// - f: impl FnMut(Complex<T>) -> Complex<T>
+/// - x: T
+/// - typ: T
+///
+/// f() must be the extension of a real function to complex arguments, written using the
+/// arithmetic and elementary functions of Complex. The derivative is computed as
+/// Im(f(x + ih))/h with h = EPS^2*max(|x|, |typ|), raised to at least T::MIN_POSITIVE so that it
+/// does not underflow to zero for tiny scales. Since no difference of nearby function values is
+/// taken, there is no subtractive cancellation and h can be made so small that the truncation
+/// error vanishes, so the result is usually accurate to machine precision. Please see the
+/// documentation of NumlError::TypError for more information on the typical value parameter.
+///
+/// f() is evaluated once. Functions which are not analytic, such as those using abs(), conj() or
+/// comparisons on the real part alone, will give wrong results.
+pub fn derivative_complex_step<T: Real>(mut f: impl FnMut(Complex<T>) -> Complex<T>, x: T, typ: T) -> Result<T, NumlError> {
+
+    check_typ(typ)?;
+
+    let h = (T::EPSILON*T::EPSILON*x.abs().max(typ.abs())).max(T::MIN_POSITIVE);
+    Ok(f(Complex::new(x, h)).im/h)
+}
+
+/// Calculates the derivative of the given function at the specified point using forward-mode
+/// automatic differentiation.
+///
+/// Inputs:
+/// - f: impl FnMut(Dual<T>) -> Dual<T>
+/// - x: T
+///
+/// f() is evaluated once at Dual::variable(x), and the derivative part of the result is returned.
+/// The easiest way to get such a function is to write it generically over Scalar, so that the
+/// same code can also be evaluated with f64 or f32:
+///
+/// ```
+/// use numl::{derivative_ad, Scalar};
+///
+/// fn cubic<T: Scalar>(x: T) -> T {
+///     x*x*x + T::from_f64(2.0)*x*x - T::from_f64(0.4)
+/// }
+///
+/// assert_eq!(cubic(1.0), 2.6);
+/// assert_eq!(derivative_ad(cubic, 1.0), 7.0);
+/// ```
+///
+/// Unlike the finite difference routines, there is no step size to choose, and the result is
+/// exact up to the rounding errors made while evaluating f() and its derivative.
+pub fn derivative_ad<T: Real>(mut f: impl FnMut(Dual<T>) -> Dual<T>, x: T) -> T {
+    f(Dual::variable(x)).eps
+}
+
+/// Checks that a typical value can be used to scale a step size: it must be nonzero (see
+/// NumlError::TypError) and a finite number.
+pub(crate) fn check_typ<T: Real>(typ: T) -> Result<(), NumlError> {
+    if typ==T::ZERO {
+        return Err(NumlError::TypError);
+    }
+    if !typ.is_finite() {
+        return Err(NumlError::InvalidInput { name: "typ", value: typ.value() });
+    }
+    Ok(())
+}
+
+/// Returns the step size EPS^(1/(n+accuracy))*max(|x|, |typ|) for a stencil approximating the
+/// nth derivative with the given order of accuracy, rounded so that x+h is exactly h away from x.
+fn step_size<T: Real>(x: T, typ: T, n: usize, accuracy: usize) -> T {
+    let h = T::EPSILON.powf(1.0/(n + accuracy) as f64)*x.abs().max(typ.abs());
+    (x + h) - x
+}
+
+/// Computes finite difference weights for the order-th derivative at x0 on arbitrary nodes, using
+/// Fornberg's algorithm.
+///
+/// Inputs:
+/// - x0: T
+/// - nodes: &[T]
+/// - order: usize
+///
+/// The returned weights w satisfy f^(order)(x0) ~= sum of w[i]*f(nodes[i]), and the
+/// approximation is exact for every polynomial of degree less than nodes.len(). The nodes do not
+/// need to be evenly spaced or sorted, and x0 does not need to be one of them, so this can be
+/// used on irregular grids and near boundaries.
+///
+/// A NumlError::StencilError is returned if there are not more nodes than the order of the
+/// derivative, or if the nodes are not distinct finite numbers.
+pub fn fd_weights<T: Real>(x0: T, nodes: &[T], order: usize) -> Result<Vec<T>, NumlError> {
+
+    if nodes.len() <= order {
+        return Err(NumlError::StencilError);
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        if !node.is_finite() || nodes[..i].contains(node) {
+            return Err(NumlError::StencilError);
+        }
+    }
+
+    let mut c = vec![vec![T::ZERO; order + 1]; nodes.len()];
+    c[0][0] = T::ONE;
+
+    let mut c1 = T::ONE;
+    let mut c4 = nodes[0] - x0;
+    for i in 1..nodes.len() {
+        let mn = usize::min(i, order);
+        let mut c2 = T::ONE;
+        let c5 = c4;
+        c4 = nodes[i] - x0;
+        for j in 0..i {
+            let c3 = nodes[i] - nodes[j];
+            c2 *= c3;
+            if j == i - 1 {
+                for k in (1..=mn).rev() {
+                    c[i][k] = c1*(T::from_f64(k as f64)*c[i-1][k-1] - c5*c[i-1][k])/c2;
+                }
+                c[i][0] = -c1*c5*c[i-1][0]/c2;
+            }
+            for k in (1..=mn).rev() {
+                c[j][k] = (c4*c[j][k] - T::from_f64(k as f64)*c[j][k-1])/c3;
+            }
+            c[j][0] = c4*c[j][0]/c3;
+        }
+        c1 = c2;
+    }
+
+    Ok(c.into_iter().map(|row| row[order]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{derivative, Scalar};
+
+    #[test]
+    fn test_derivative_richardson_exp() {
+        let result = derivative_richardson(f64::exp, 1.0, 1.0).unwrap();
+        let exact = f64::exp(1.0);
+        assert!((result.derivative - exact).abs() < 1e-12);
+        assert!(result.error < 1e-10);
+    }
+
+    #[test]
+    fn test_derivative_richardson_beats_derivative() {
+        let exact = f64::cos(0.7);
+        let richardson = derivative_richardson(f64::sin, 0.7, 1.0).unwrap();
+        let central = derivative(f64::sin, 0.7, 1.0).unwrap();
+        assert!((richardson.derivative - exact).abs() < 1e-12);
+        assert!((richardson.derivative - exact).abs() < (central - exact).abs());
+    }
+
+    #[test]
+    fn test_derivative_richardson_error_estimate() {
+        let exact = -4.0*f64::exp(-4.0);
+        let result = derivative_richardson(|x: f64| f64::exp(-x*x), 2.0, 1.0).unwrap();
+        assert!((result.derivative - exact).abs() <= 10.0*result.error + 1e-16);
+    }
+
+    #[test]
+    fn test_derivative_richardson_typ_error() {
+        let result = derivative_richardson(f64::exp, 1.0, 0.0);
+        assert!(matches!(result, Err(NumlError::TypError)));
+    }
+
+    #[test]
+    fn test_fd_weights() {
+        assert_eq!(fd_weights(0.0, &[-1.0, 0.0, 1.0], 1).unwrap(), vec![-0.5, 0.0, 0.5]);
+        assert_eq!(fd_weights(0.0, &[-1.0, 0.0, 1.0], 2).unwrap(), vec![1.0, -2.0, 1.0]);
+        assert_eq!(fd_weights(0.0, &[-2.0, -1.0, 0.0, 1.0, 2.0], 4).unwrap(), vec![1.0, -4.0, 6.0, -4.0, 1.0]);
+        assert_eq!(fd_weights(0.0, &[0.0, 1.0, 2.0], 1).unwrap(), vec![-1.5, 2.0, -0.5]);
+    }
+
+    #[test]
+    fn test_fd_weights_irregular_grid() {
+        // Off-centre x0 on an unsorted, unevenly spaced grid must differentiate quadratics exactly.
+        let nodes = [0.3, -0.1, 1.2, 0.7];
+        let x0 = 0.45;
+        let weights = fd_weights(x0, &nodes, 2).unwrap();
+        let quadratic = |x: f64| 3.0*x*x - x + 2.0;
+        let second: f64 = nodes.iter().zip(&weights).map(|(x, w)| w*quadratic(*x)).sum();
+        assert!((second - 6.0).abs() < 1e-12);
+
+        let weights = fd_weights(x0, &nodes, 0).unwrap();
+        let value: f64 = nodes.iter().zip(&weights).map(|(x, w)| w*quadratic(*x)).sum();
+        assert!((value - quadratic(x0)).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_fd_weights_errors() {
+        assert!(matches!(fd_weights(0.0, &[0.0, 1.0], 2), Err(NumlError::StencilError)));
+        assert!(matches!(fd_weights(0.0, &[0.0, 1.0, 0.0], 1), Err(NumlError::StencilError)));
+        assert!(matches!(fd_weights(0.0, &[0.0, f64::NAN, 1.0], 1), Err(NumlError::StencilError)));
+        assert!(matches!(fd_weights(0.0, &[], 0), Err(NumlError::StencilError)));
+    }
+
+    #[test]
+    fn test_stencil_derivative_one_sided() {
+        // Only evaluates sqrt() to the right of zero.
+        let forward = stencil_derivative(f64::sqrt, 1e-3, &[0.0, 1.0, 2.0], 1, 1e-3).unwrap();
+        assert!((forward - 0.5/f64::sqrt(1e-3)).abs() < 1e-6);
+
+        let backward = stencil_derivative(f64::exp, 1.0, &[0.0, -1.0, -2.0, -3.0], 2, 1.0).unwrap();
+        assert!((backward - f64::exp(1.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_nth_derivative_polynomial() {
+        let quartic = |x: f64| x*x*x*x - 3.0*x*x*x + x;
+        let x = 1.5;
+        let second = nth_derivative(quartic, x, 2, 1.0).unwrap();
+        let third = nth_derivative(quartic, x, 3, 1.0).unwrap();
+        let fourth = nth_derivative(quartic, x, 4, 1.0).unwrap();
+        assert!((second - (12.0*x*x - 18.0*x)).abs() < 1e-7);
+        assert!((third - (24.0*x - 18.0)).abs() < 1e-5);
+        assert!((fourth - 24.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_nth_derivative_transcendental() {
+        let x = 0.3;
+        assert!((nth_derivative(f64::sin, x, 2, 1.0).unwrap() + x.sin()).abs() < 1e-8);
+        assert!((nth_derivative(f64::sin, x, 3, 1.0).unwrap() + x.cos()).abs() < 1e-6);
+        assert!((nth_derivative(f64::exp, x, 4, 1.0).unwrap() - x.exp()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_nth_derivative_with_accuracy() {
+        let x = 0.3;
+        let low = nth_derivative_with_accuracy(f64::exp, x, 4, 2, 1.0).unwrap();
+        let high = nth_derivative_with_accuracy(f64::exp, x, 4, 6, 1.0).unwrap();
+        assert!((high - x.exp()).abs() < (low - x.exp()).abs());
+        assert!((high - x.exp()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_nth_derivative_first_matches_derivative() {
+        let cubic = |x: f64| (x*x*x) + (2.0*x*x) - 0.4;
+        let nth = nth_derivative(cubic, 1.0, 1, 0.5).unwrap();
+        let first = derivative(cubic, 1.0, 0.5).unwrap();
+        assert!((nth - first).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_nth_derivative_zeroth() {
+        assert_eq!(nth_derivative(f64::exp, 1.0, 0, 1.0).unwrap(), f64::exp(1.0));
+    }
+
+    #[test]
+    fn test_derivative_forward_backward() {
+        let x = 1.0;
+        let exact = f64::exp(1.0);
+        let forward1 = derivative_forward(f64::exp, x, 1, 1.0).unwrap();
+        let forward2 = derivative_forward(f64::exp, x, 2, 1.0).unwrap();
+        let backward2 = derivative_backward(f64::exp, x, 2, 1.0).unwrap();
+        assert!((forward1 - exact).abs() < 1e-7);
+        assert!((forward2 - exact).abs() < 1e-9);
+        assert!((backward2 - exact).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_derivative_forward_stays_right() {
+        let mut smallest = f64::INFINITY;
+        let mut evaluations = 0;
+        let result = derivative_forward(|x: f64| { evaluations += 1; smallest = smallest.min(x); x.ln() }, 1e-3, 2, 1e-3).unwrap();
+        assert!((result - 1e3).abs() < 1e-5);
+        assert_eq!(smallest, 1e-3);
+        assert_eq!(evaluations, 3);
+    }
+
+    #[test]
+    fn test_derivative_in_domain() {
+        // At the boundary the forward stencil is used, in the interior the central one.
+        let boundary = derivative_in_domain(f64::sqrt, 1.0, 1.0, 1.0..=f64::INFINITY).unwrap();
+        assert!((boundary - 0.5).abs() < 1e-9);
+        let interior = derivative_in_domain(f64::sqrt, 1.0, 1.0, 0.0..=f64::INFINITY).unwrap();
+        assert_eq!(interior, derivative(f64::sqrt, 1.0, 1.0).unwrap());
+
+        let mut largest = f64::NEG_INFINITY;
+        let upper = derivative_in_domain(|x: f64| { largest = largest.max(x); (1.0 - x).sqrt() }, 0.75, 1.0, 0.0..=0.75).unwrap();
+        assert!((upper + 1.0).abs() < 1e-9);
+        assert_eq!(largest, 0.75);
+    }
+
+    #[test]
+    fn test_derivative_in_domain_errors() {
+        assert!(matches!(derivative_in_domain(f64::ln, -1.0, 1.0, 0.0..=f64::INFINITY), Err(NumlError::DomainError)));
+        assert!(matches!(derivative_in_domain(f64::ln, 1.0, 1.0, 1.0..=1.0), Err(NumlError::DomainError)));
+        assert!(matches!(derivative_in_domain(f64::ln, 1.0, 0.0, 0.0..=2.0), Err(NumlError::TypError)));
+    }
+
+    /// Squire and Trapp's test function, whose derivative is hard to approximate by differences.
+    fn squire_trapp(x: f64) -> f64 {
+        x.exp()/(x.sin().powi(3) + x.cos().powi(3)).sqrt()
+    }
+
+    fn squire_trapp_complex(x: Complex) -> Complex {
+        x.exp()/(x.sin().powi(3) + x.cos().powi(3)).sqrt()
+    }
+
+    fn squire_trapp_derivative(x: f64) -> f64 {
+        let g = x.sin().powi(3) + x.cos().powi(3);
+        let dg = 3.0*x.sin()*x.cos()*(x.sin() - x.cos());
+        squire_trapp(x)*(1.0 - dg/(2.0*g))
+    }
+
+    #[test]
+    fn test_derivative_complex_step() {
+        let x = 1.5;
+        let exact = squire_trapp_derivative(x);
+        let complex_step = derivative_complex_step(squire_trapp_complex, x, 1.0).unwrap();
+        let central = derivative(squire_trapp, x, 1.0).unwrap();
+        assert!((complex_step - exact).abs() <= 4.0*f64::EPSILON*exact.abs());
+        assert!((complex_step - exact).abs()*1e4 < (central - exact).abs());
+    }
+
+    #[test]
+    fn test_derivative_complex_step_large_offset() {
+        // The large constant swamps the change in f() over a finite difference step, but does
+        // not touch the imaginary part.
+        let x = 0.5;
+        let complex_step = derivative_complex_step(|z: Complex| z.sin() + 1e10, x, 1.0).unwrap();
+        let central = derivative(|x: f64| x.sin() + 1e10, x, 1.0).unwrap();
+        assert_eq!(complex_step, x.cos());
+        assert!((central - x.cos()).abs() > 1e-3);
+    }
+
+    #[test]
+    fn test_derivative_complex_step_tiny_scale() {
+        // EPS^2 times the scale underflows to zero here.
+        assert_eq!(derivative_complex_step(|z: Complex| z*z, 0.0, 1e-300).unwrap(), 0.0);
+        assert_eq!(derivative_complex_step(|z: Complex| z.exp(), 0.0, 1e-300).unwrap(), 1.0);
+        assert_eq!(derivative_complex_step(|z: Complex| z.sin(), 3e-300, 1e-300).unwrap(), 1.0);
+        assert_eq!(derivative_complex_step(|z: Complex<f32>| z*z + z, 0.0, 1e-30).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_derivative_complex_step_typ_error() {
+        assert!(matches!(derivative_complex_step(|z: Complex| z.exp(), 1.0, 0.0), Err(NumlError::TypError)));
+    }
+
+    #[test]
+    fn test_derivative_ad() {
+        fn sample_cubic<T: Scalar>(x: T) -> T {
+            x*x*x + T::from_f64(2.0)*x*x - T::from_f64(0.4)
+        }
+
+        assert_eq!(derivative_ad(sample_cubic, 1.0), 7.0);
+        assert_eq!(derivative_ad(sample_cubic, -1.0), -1.0);
+        let central = derivative(sample_cubic, 1.0, 0.5).unwrap();
+        assert!((central - derivative_ad(sample_cubic, 1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_derivative_ad_squire_trapp() {
+        fn squire_trapp_generic<T: Scalar>(x: T) -> T {
+            x.exp()/(x.sin().powi(3) + x.cos().powi(3)).sqrt()
+        }
+
+        let x = 1.5;
+        let exact = squire_trapp_derivative(x);
+        assert_eq!(squire_trapp_generic(x), squire_trapp(x));
+        assert!((derivative_ad(squire_trapp_generic, x) - exact).abs() <= 4.0*f64::EPSILON*exact.abs());
+    }
+}
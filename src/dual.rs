@@ -0,0 +1,359 @@
+//! Dual numbers for forward-mode automatic differentiation.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::{Real, Scalar};
+
+/// A dual number re + eps*e, where e*e = 0.
+///
+/// Evaluating a function at Dual::variable(x) propagates the derivative alongside the value
+/// through every operation, so that f(Dual::variable(x)) = f(x) + f'(x)*e exactly, up to the
+/// rounding errors of evaluating f() and f'() themselves. There is no step size and therefore no
+/// truncation error.
+///
+/// The parts can be of any type implementing Real, and default to f64. Comparisons, including ==,
+/// only look at re, so that a dual number behaves like its value in tests such as x == 0 or x < y.
+/// Compare the fields directly to also take eps into account.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dual<T = f64> {
+    /// The value.
+    pub re: T,
+
+    /// The derivative with respect to the independent variable.
+    pub eps: T,
+}
+
+impl<T: Real> Dual<T> {
+    /// Creates the dual number re + eps*e.
+    pub const fn new(re: T, eps: T) -> Self {
+        Dual { re, eps }
+    }
+
+    /// Creates the independent variable x, whose derivative with respect to itself is one.
+    pub const fn variable(x: T) -> Self {
+        Dual::new(x, T::ONE)
+    }
+
+    /// Creates a constant, whose derivative is zero.
+    pub const fn constant(c: T) -> Self {
+        Dual::new(c, T::ZERO)
+    }
+
+    /// Applies the chain rule for a function with value fx and derivative dfx at re.
+    fn chain(self, fx: T, dfx: T) -> Self {
+        Dual::new(fx, dfx*self.eps)
+    }
+}
+
+impl<T: Real> From<T> for Dual<T> {
+    fn from(c: T) -> Self {
+        Dual::constant(c)
+    }
+}
+
+impl<T: Real> PartialEq for Dual<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.re == other.re
+    }
+}
+
+impl<T: Real> PartialOrd for Dual<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.re.partial_cmp(&other.re)
+    }
+}
+
+impl<T: Real> fmt::Display for Dual<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.eps.is_sign_negative() {
+            write!(f, "{}-{}e", self.re, -self.eps)
+        } else {
+            write!(f, "{}+{}e", self.re, self.eps)
+        }
+    }
+}
+
+impl<T: Real> Neg for Dual<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Dual::new(-self.re, -self.eps)
+    }
+}
+
+impl<T: Real> Add for Dual<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Dual::new(self.re + rhs.re, self.eps + rhs.eps)
+    }
+}
+
+impl<T: Real> Sub for Dual<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Dual::new(self.re - rhs.re, self.eps - rhs.eps)
+    }
+}
+
+impl<T: Real> Mul for Dual<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Dual::new(self.re*rhs.re, self.eps*rhs.re + self.re*rhs.eps)
+    }
+}
+
+impl<T: Real> Div for Dual<T> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let quotient = self.re/rhs.re;
+        Dual::new(quotient, (self.eps - quotient*rhs.eps)/rhs.re)
+    }
+}
+
+impl<T: Real> Add<T> for Dual<T> {
+    type Output = Self;
+
+    fn add(self, rhs: T) -> Self {
+        Dual::new(self.re + rhs, self.eps)
+    }
+}
+
+impl<T: Real> Sub<T> for Dual<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: T) -> Self {
+        Dual::new(self.re - rhs, self.eps)
+    }
+}
+
+impl<T: Real> Mul<T> for Dual<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        Dual::new(self.re*rhs, self.eps*rhs)
+    }
+}
+
+impl<T: Real> Div<T> for Dual<T> {
+    type Output = Self;
+
+    fn div(self, rhs: T) -> Self {
+        Dual::new(self.re/rhs, self.eps/rhs)
+    }
+}
+
+/// Implements arithmetic with a real number on the left and a dual number on the right, which the
+/// orphan rules only allow for concrete types.
+macro_rules! impl_real_lhs_ops {
+    ($($t:ty),*) => {$(
+        impl Add<Dual<$t>> for $t {
+            type Output = Dual<$t>;
+
+            fn add(self, rhs: Dual<$t>) -> Dual<$t> {
+                rhs + self
+            }
+        }
+
+        impl Sub<Dual<$t>> for $t {
+            type Output = Dual<$t>;
+
+            fn sub(self, rhs: Dual<$t>) -> Dual<$t> {
+                Dual::new(self - rhs.re, -rhs.eps)
+            }
+        }
+
+        impl Mul<Dual<$t>> for $t {
+            type Output = Dual<$t>;
+
+            fn mul(self, rhs: Dual<$t>) -> Dual<$t> {
+                rhs*self
+            }
+        }
+
+        impl Div<Dual<$t>> for $t {
+            type Output = Dual<$t>;
+
+            fn div(self, rhs: Dual<$t>) -> Dual<$t> {
+                Dual::constant(self)/rhs
+            }
+        }
+    )*};
+}
+
+impl_real_lhs_ops!(f32, f64);
+
+impl<T: Real> AddAssign for Dual<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Real> SubAssign for Dual<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T: Real> MulAssign for Dual<T> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self*rhs;
+    }
+}
+
+impl<T: Real> DivAssign for Dual<T> {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self/rhs;
+    }
+}
+
+impl<T: Real> Scalar for Dual<T> {
+    fn from_f64(value: f64) -> Self {
+        Dual::constant(T::from_f64(value))
+    }
+
+    fn value(self) -> f64 {
+        self.re.value()
+    }
+
+    fn abs(self) -> Self {
+        if self.re < T::ZERO { -self } else { self }
+    }
+
+    fn sqrt(self) -> Self {
+        let root = self.re.sqrt();
+        self.chain(root, T::from_f64(0.5)/root)
+    }
+
+    fn cbrt(self) -> Self {
+        let root = self.re.cbrt();
+        self.chain(root, T::ONE/(T::from_f64(3.0)*root*root))
+    }
+
+    fn exp(self) -> Self {
+        let exp = self.re.exp();
+        self.chain(exp, exp)
+    }
+
+    fn ln(self) -> Self {
+        self.chain(self.re.ln(), T::ONE/self.re)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        if n == 0 {
+            return Dual::constant(T::ONE);
+        }
+        self.chain(self.re.powi(n), T::from_f64(n as f64)*self.re.powi(n - 1))
+    }
+
+    fn powf(self, p: f64) -> Self {
+        if p == 0.0 {
+            return Dual::constant(T::ONE);
+        }
+        self.chain(self.re.powf(p), T::from_f64(p)*self.re.powf(p - 1.0))
+    }
+
+    fn sin(self) -> Self {
+        self.chain(self.re.sin(), self.re.cos())
+    }
+
+    fn cos(self) -> Self {
+        self.chain(self.re.cos(), -self.re.sin())
+    }
+
+    fn tan(self) -> Self {
+        let tan = self.re.tan();
+        self.chain(tan, T::ONE + tan*tan)
+    }
+
+    fn asin(self) -> Self {
+        self.chain(self.re.asin(), T::ONE/(T::ONE - self.re*self.re).sqrt())
+    }
+
+    fn acos(self) -> Self {
+        self.chain(self.re.acos(), -T::ONE/(T::ONE - self.re*self.re).sqrt())
+    }
+
+    fn atan(self) -> Self {
+        self.chain(self.re.atan(), T::ONE/(T::ONE + self.re*self.re))
+    }
+
+    fn sinh(self) -> Self {
+        self.chain(self.re.sinh(), self.re.cosh())
+    }
+
+    fn cosh(self) -> Self {
+        self.chain(self.re.cosh(), self.re.sinh())
+    }
+
+    fn tanh(self) -> Self {
+        let tanh = self.re.tanh();
+        self.chain(tanh, T::ONE - tanh*tanh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 4.0*f64::EPSILON*f64::max(1.0, b.abs())
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let x: Dual = Dual::variable(3.0);
+        let y = x*x - 2.0*x + 1.0;
+        assert_eq!((y.re, y.eps), (4.0, 4.0));
+        let z = 1.0/x;
+        assert!(close(z.eps, -1.0/9.0));
+        let w = (x + 1.0)/(x - 1.0);
+        assert!(close(w.eps, -2.0/4.0));
+    }
+
+    #[test]
+    fn test_elementary_functions() {
+        let x = 0.4;
+        let d = |f: fn(Dual) -> Dual| f(Dual::variable(x)).eps;
+        assert!(close(d(Scalar::exp), x.exp()));
+        assert!(close(d(Scalar::ln), 1.0/x));
+        assert!(close(d(Scalar::sqrt), 0.5/x.sqrt()));
+        assert!(close(d(Scalar::cbrt), 1.0/(3.0*x.cbrt().powi(2))));
+        assert!(close(d(Scalar::sin), x.cos()));
+        assert!(close(d(Scalar::cos), -x.sin()));
+        assert!(close(d(Scalar::tan), 1.0/x.cos().powi(2)));
+        assert!(close(d(Scalar::asin), 1.0/(1.0 - x*x).sqrt()));
+        assert!(close(d(Scalar::acos), -1.0/(1.0 - x*x).sqrt()));
+        assert!(close(d(Scalar::atan), 1.0/(1.0 + x*x)));
+        assert!(close(d(Scalar::sinh), x.cosh()));
+        assert!(close(d(Scalar::cosh), x.sinh()));
+        assert!(close(d(Scalar::tanh), 1.0/x.cosh().powi(2)));
+        assert!(close(Dual::variable(x).powi(3).eps, 3.0*x*x));
+        assert!(close(Dual::variable(x).powf(2.5).eps, 2.5*x.powf(1.5)));
+        assert!(close((-Dual::variable(x)).abs().eps, 1.0));
+    }
+
+    #[test]
+    fn test_comparison_uses_value() {
+        assert!(Dual::new(1.0, 5.0) < Dual::new(2.0, -5.0));
+        // == agrees with partial_cmp() and ignores the derivative.
+        let (a, b) = (Dual::new(1.0, 5.0), Dual::new(1.0, -5.0));
+        assert_eq!(a.partial_cmp(&b), Some(Ordering::Equal));
+        assert_eq!(a, b);
+        assert!(a <= b);
+        assert_ne!(a, Dual::new(2.0, 5.0));
+    }
+
+    #[test]
+    fn test_f32() {
+        let x = Dual::variable(2.0f32);
+        let y = x*x*x - 3.0f32*x;
+        assert_eq!((y.re, y.eps), (2.0, 9.0));
+        assert!((x.sin().eps - 2.0f32.cos()).abs() <= f32::EPSILON);
+    }
+}
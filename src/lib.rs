@@ -3,6 +3,44 @@
 
 use thiserror::Error;
 
+mod bracket;
+mod complex;
+mod diff;
+mod dual;
+mod multivariate;
+mod noise;
+mod polynomial;
+mod real;
+mod reverse;
+mod roots;
+mod scalar;
+mod scan;
+mod solver;
+
+pub use bracket::{bisect, brent, itp, ItpOptions};
+pub use complex::Complex;
+pub use diff::{
+    derivative_ad, derivative_backward, derivative_complex_step, derivative_forward, derivative_in_domain,
+    derivative_richardson, fd_weights, nth_derivative, nth_derivative_with_accuracy, stencil_derivative,
+    DerivativeEstimate,
+};
+pub use dual::Dual;
+pub use multivariate::{
+    color_columns, gradient, hessian, jacobian, sparse_jacobian, DifferenceMode, FdReport, SparseMatrix,
+};
+pub use noise::{derivative_noisy, estimate_noise, NoisyDerivative};
+pub use polynomial::{polynomial_roots, Polynomial, PolynomialRoot};
+pub use real::Real;
+pub use reverse::{gradient_ad, Gradient, Tape, Var};
+pub use roots::{
+    damped_newton, deflated_newton, halley, householder, multiple_root_newton, newton, newton_solve, DampedNewtonOptions,
+    DampedNewtonReport, DeflatedRoot, DeflationOptions, DeflationReport, MultipleRootReport, NewtonOptions, RootReport,
+    Termination,
+};
+pub use scalar::Scalar;
+pub use scan::{find_roots, RootKind, Sampling, ScanOptions, ScanReport, ScannedRoot};
+pub use solver::{AndersonBjorck, Bisection, Brent, Illinois, Itp, Pegasus, Ridders, RootSolver, SafeNewton, Secant};
+
 /// Enum of errors that can be returned by numl functions.
 #[derive(Error, Debug)]
 pub enum NumlError {
@@ -15,6 +53,7 @@ pub enum NumlError {
     ///
     /// It is recommended that you also avoid passing typical values with very small magnitude into
     /// these functions, though doing so will not return an error unless the value is exactly zero.
+    /// A typical value which is NaN or infinite results in a NumlError::InvalidInput.
     #[error("Typ must be positive")]
     TypError,
 
@@ -23,64 +62,256 @@ pub enum NumlError {
     /// in which case a zero derivative would cause an erroneous divide-by-zero.
     #[error("Derivative calculated to zero, but needs to be nonzero")]
     DerivativeZeroError,
+
+    /// Error for when a finite difference stencil cannot be built from the given nodes.
+    ///
+    /// Approximating the nth derivative requires at least n+1 nodes, and the nodes must be
+    /// distinct finite numbers.
+    #[error("Nodes must be distinct, finite and more numerous than the derivative order")]
+    StencilError,
+
+    /// Error for when a function cannot be evaluated at the points an algorithm needs, because
+    /// they would fall outside of the domain the caller specified.
+    ///
+    /// This is returned when the point of interest itself lies outside of the domain, or when the
+    /// domain is too narrow to fit the points required by the algorithm.
+    #[error("Evaluation points do not fit inside the given domain")]
+    DomainError,
+
+    /// Error for when the lengths of slices or vectors passed into or returned from a function of
+    /// several variables do not match each other, for example a typical value slice whose length
+    /// differs from that of the point.
+    #[error("Dimensions of inputs or outputs do not match")]
+    DimensionMismatch,
+
+    /// Error for when the noise level of a function cannot be estimated.
+    ///
+    /// This happens when the function takes the same value at every sample point, in which case
+    /// the samples are too close together and typ should be increased, or when the function
+    /// changes too much between the sample points for the noise to be visible, in which case
+    /// typ should be decreased.
+    #[error("Could not estimate the noise level of the function")]
+    NoiseEstimationError,
+
+    /// Error for when an input parameter is not a usable number, for example a typical value that
+    /// is NaN or infinite.
+    ///
+    /// name is the name of the offending parameter and value is the value that was passed,
+    /// converted to f64.
+    #[error("Invalid value {value} for input {name}")]
+    InvalidInput {
+        name: &'static str,
+        value: f64,
+    },
+
+    /// Error returned by bracketing root finders when f(a) and f(b) are nonzero and have the same
+    /// sign, so that [a, b] is not guaranteed to contain a root.
+    ///
+    /// The interval may still contain an even number of roots; scanning it at a finer resolution
+    /// for a sign change gives a usable bracket.
+    #[error("f(a) and f(b) must have opposite signs (f({a}) = {fa}, f({b}) = {fb})")]
+    InvalidBracket {
+        a: f64,
+        b: f64,
+        fa: f64,
+        fb: f64,
+    },
+
+    /// Error returned by root finders when f(x) or its derivative evaluates to NaN or an
+    /// infinity at a finite x, which usually means that x has left the domain of f().
+    ///
+    /// derivative is NaN if the function value was already unusable, in which case the
+    /// derivative is not computed.
+    #[error("Non-finite value at x = {x} (f(x) = {fx}, f'(x) = {derivative})")]
+    NonFiniteValue {
+        x: f64,
+        fx: f64,
+        derivative: f64,
+    },
+
+    /// Error returned by Newton-type methods when the step |f(x)/f'(x)| exceeds the configured
+    /// bound relative to max(|x|, |typ|).
+    ///
+    /// This catches derivatives which are not exactly zero but close enough to it that the step
+    /// would throw the iterate far away from where the local model of f() is meaningful, which
+    /// is what usually happens near a local extremum of f(). A DerivativeZeroError is returned
+    /// instead when the derivative is exactly zero.
+    #[error("Ill-conditioned step at x = {x} (f(x) = {fx}, f'(x) = {derivative})")]
+    IllConditioned {
+        x: f64,
+        fx: f64,
+        derivative: f64,
+    },
+
+    /// Error returned by damped_newton() when no fraction of the Newton step down to the minimum
+    /// step fraction decreases |f| enough.
+    ///
+    /// This usually means that x is close to a local minimum of |f| which is not a root, or to a
+    /// discontinuity of f(). x, fx and derivative describe the point the line search started from.
+    #[error("Line search found no acceptable step at x = {x} (f(x) = {fx}, f'(x) = {derivative})")]
+    LineSearchFailed {
+        x: f64,
+        fx: f64,
+        derivative: f64,
+    },
+
+    /// Error returned by deflated_newton() when the deflated function converged to a point which
+    /// is not a root of the original function.
+    ///
+    /// Dividing out the known roots makes the deflated function tend to zero wherever it grows more
+    /// slowly than the product of the divided out factors, so the iteration can run off towards
+    /// such a region instead of finding another root. fx is the absolute value of the original
+    /// function at x.
+    #[error("Deflated function converged to x = {x}, which is not a root (f(x) = {fx})")]
+    SpuriousRoot {
+        x: f64,
+        fx: f64,
+    },
+
+    /// Error returned by iterative solvers which used up their iteration budget without meeting
+    /// any of their stopping criteria.
+    ///
+    /// x is the last iterate that was computed, and fx and derivative are f(x) and f'(x) at that
+    /// iterate, which can be used to decide whether the result is still good enough for your
+    /// purposes. All three are converted to f64 whatever type the solver was run with.
+    #[error("Failed to converge after {iterations} iterations (x = {x}, f(x) = {fx}, f'(x) = {derivative})")]
+    NoConvergence {
+        iterations: usize,
+        x: f64,
+        fx: f64,
+        derivative: f64,
+    },
+
+    /// Error returned by iterative solvers when an iterate stops being a finite number, which
+    /// usually means that the iteration has run away from the root.
+    ///
+    /// x is the last finite iterate before the divergence was detected, converted to f64.
+    #[error("Iteration diverged after {iterations} iterations (last finite x = {x})")]
+    Divergence {
+        iterations: usize,
+        x: f64,
+    },
 }
 
 /// Numerically calculates the derivative of the given function at the specified point.
 ///
 /// Inputs:
-/// - f: fn(f64) -> f64
-/// - x: f64
-/// - typ: f64
+/// - f: impl Fn(T) -> T
+/// - x: T
+/// - typ: T
 ///
 /// f() is the function whose derivative is being computed, x is the point at which that
 /// derivative is computed, and typ is the typical size of x (in the event that the passed value of
 /// x is very different from the usual size that x takes on). Please see the documentation of
 /// NumlError::TypError for more information on the typical value parameter.
 ///
-/// f() is expected to be a pure function, and this algorithm will do two evaluations of the
-/// function in order to determine the derivative.
-pub fn derivative(f: fn(f64) -> f64, x: f64, typ: f64) -> Result<f64, NumlError> {
-
-    if typ==0.0 {
-        return Err(NumlError::TypError); 
-    }
-
-    let h:f64 = if x.abs() > typ.abs() { 
-        f64::cbrt(f64::EPSILON)*x
-    } else {
-        f64::cbrt(f64::EPSILON)*typ
-    };
+/// The computation works with any floating point type implementing Real, and the step size is
+/// derived from that type's machine epsilon, so f32 and f64 both get as much accuracy as they can
+/// hold.
+///
+/// f() may be a plain function or a closure capturing its parameters. It is expected to be a pure
+/// function, and this algorithm will do two evaluations of the function in order to determine the
+/// derivative. If f() needs to mutate its captured state (for example to cache or count
+/// evaluations), use derivative_mut() instead.
+pub fn derivative<T: Real>(f: impl Fn(T) -> T, x: T, typ: T) -> Result<T, NumlError> {
+    derivative_mut(f, x, typ)
+}
 
-    Ok((f(x+h) - f(x-h))/(2.0*h))
+/// Numerically calculates the derivative of the given function at the specified point, allowing
+/// the function to mutate its captured state.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - typ: T
+///
+/// This behaves exactly like derivative(), but accepts closures which need mutable access to the
+/// values they capture, such as functions that cache results or count how many times they have
+/// been evaluated. f() is evaluated exactly twice, at x+h and at x-h, in that order.
+///
+/// This is the two-point central stencil of stencil_derivative(), whose step size
+/// h = cbrt(EPS)*max(|x|, |typ|) balances the O(h^2) truncation error against the O(EPS/h)
+/// round-off error.
+pub fn derivative_mut<T: Real>(f: impl FnMut(T) -> T, x: T, typ: T) -> Result<T, NumlError> {
+    diff::stencil_derivative(f, x, &[T::ONE, -T::ONE], 1, typ)
 }
 
 /// Performs one iteration of a quasi-Newton's method and returns the result.
 ///
 /// Inputs:
-/// - f: fn(f64) -> f64
-/// - x: f64
-/// - typ: f64
+/// - f: impl Fn(T) -> T
+/// - x: T
+/// - typ: T
 ///
 /// f() is the function whose root is being computed, x is the current guess of the root, 
 /// and typ is the typical size of x (in the event that the passed value of
 /// x is very different from the usual size that x takes on). Please see the documentation of
 /// NumlError::TypError for more information on the typical value parameter.
 ///
-/// f() is expected to be a pure function, and this algorithm will do three evaluations of the
-/// function in order to determine the derivative.
+/// f() may be a plain function or a closure capturing its parameters. It is expected to be a pure
+/// function, and this algorithm will do three evaluations of the function in order to determine
+/// the derivative. If f() needs to mutate its captured state, use nqn_mut() instead.
 ///
 /// If the derivative of f() at the specified point is evaluated to be exactly a floating point
-/// zero, a NumlError::DerivativeZeroError will be returned. However, no error will be returned if
-/// the derivative evalutes to a number very close to zero, which may cause issues. It is thus
-/// recommended to check if your function has a derivative zero near the input value if you are
-/// getting unexplainable behavior.
-pub fn nqn(f: fn(f64) -> f64, x: f64, typ: f64) -> Result<f64, NumlError> { 
-    let computed_derivative:f64 = match derivative(f, x, typ) {
-        Ok(0.0) => return Err(NumlError::DerivativeZeroError),
-        Ok(g) => g,
-        Err(g) => return Err(g)
-    };
-    Ok((x) - (f(x))/(computed_derivative))
+/// zero, a NumlError::DerivativeZeroError will be returned. A derivative which is merely very
+/// close to zero would produce a huge jump, so a NumlError::IllConditioned is returned when the
+/// step |f(x)/f'(x)| exceeds 1/sqrt(EPS) times max(|x|, |typ|); use nqn_with_max_step() to choose
+/// a different bound. If f(x) or the derivative is NaN or infinite, a NumlError::NonFiniteValue is
+/// returned. All of these errors carry x, f(x) and the derivative where they are known.
+pub fn nqn<T: Real>(f: impl Fn(T) -> T, x: T, typ: T) -> Result<T, NumlError> { 
+    nqn_mut(f, x, typ)
+}
+
+/// Performs one iteration of a quasi-Newton's method, allowing the function to mutate its
+/// captured state.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - typ: T
+///
+/// This behaves exactly like nqn(), but accepts closures which need mutable access to the values
+/// they capture. f() is evaluated exactly three times: twice by derivative_mut() and once at x.
+pub fn nqn_mut<T: Real>(f: impl FnMut(T) -> T, x: T, typ: T) -> Result<T, NumlError> { 
+    nqn_with_max_step(f, x, typ, NewtonOptions::<T>::default().max_step)
+}
+
+/// Performs one iteration of a quasi-Newton's method with a custom bound on the step size.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - typ: T
+/// - max_step: T
+///
+/// This behaves exactly like nqn_mut(), but returns a NumlError::IllConditioned whenever the step
+/// |f(x)/f'(x)| exceeds max_step*max(|x|, |typ|), instead of using the default bound of
+/// 1/sqrt(EPS). Passing T::INFINITY disables the check.
+pub fn nqn_with_max_step<T: Real>(mut f: impl FnMut(T) -> T, x: T, typ: T, max_step: T) -> Result<T, NumlError> {
+    let computed_derivative:T = derivative_mut(&mut f, x, typ)?;
+    roots::newton_step(x, f(x), computed_derivative, typ, max_step)
+}
+
+/// Performs one iteration of Newton's method using a derivative computed by automatic
+/// differentiation, and returns the result.
+///
+/// Inputs:
+/// - f: impl FnMut(Dual<T>) -> Dual<T>
+/// - x: T
+///
+/// This is the counterpart of nqn() for functions that can be evaluated with Dual numbers, which
+/// is easiest done by writing them generically over Scalar (see derivative_ad()). A single
+/// evaluation of f() yields both f(x) and the exact derivative, so no typical value is needed and
+/// the step is the true Newton step rather than an approximation of it.
+///
+/// If the derivative of f() at the specified point is exactly zero, a
+/// NumlError::DerivativeZeroError will be returned.
+pub fn nqn_ad<T: Real>(mut f: impl FnMut(Dual<T>) -> Dual<T>, x: T) -> Result<T, NumlError> {
+    let result = f(Dual::variable(x));
+    if result.eps == T::ZERO {
+        return Err(NumlError::DerivativeZeroError);
+    }
+    Ok(x - result.re/result.eps)
 }
 
 #[cfg(test)]
@@ -91,6 +322,10 @@ mod tests {
         (x*x*x) + (2.0*x*x) - 0.4
     }
 
+    fn sample_cubic_generic<T: Scalar>(x: T) -> T {
+        (x*x*x) + (T::from_f64(2.0)*x*x) - T::from_f64(0.4)
+    }
+
     #[test]
     fn test_derivative() {
         let result = derivative(sample_cubic, 1.0, 0.5).unwrap();
@@ -123,5 +358,110 @@ mod tests {
         }
         assert!(guess > 0.4 && guess < 0.41);
     }
-    
+
+    #[test]
+    fn test_derivative_capturing_closure() {
+        let coefficients = [-0.4, 0.0, 2.0, 1.0];
+        let poly = |x: f64| coefficients.iter().rev().fold(0.0, |acc, c| acc*x + c);
+        let result = derivative(poly, 1.0, 0.5).unwrap();
+        assert!(result > 6.9 && result < 7.1);
+    }
+
+    #[test]
+    fn test_derivative_mut_counts_evaluations() {
+        let mut evaluations = 0;
+        let counted = |x: f64| {
+            evaluations += 1;
+            sample_cubic(x)
+        };
+        let result = derivative_mut(counted, 1.0, 0.5).unwrap();
+        assert!(result > 6.9 && result < 7.1);
+        assert_eq!(evaluations, 2);
+    }
+
+    #[test]
+    fn test_derivative_mut_typ_error() {
+        let mut evaluations = 0;
+        let result = derivative_mut(|x: f64| { evaluations += 1; x }, 1.0, 0.0);
+        assert!(matches!(result, Err(NumlError::TypError)));
+        assert_eq!(evaluations, 0);
+    }
+
+    #[test]
+    fn test_nqn_capturing_closure() {
+        let shift = 2.0;
+        let shifted = |x: f64| x*x - shift;
+        let mut guess = 1.0;
+        for _i in 1..12 {
+            guess = nqn(shifted, guess, 1.0).unwrap();
+        }
+        assert!((guess - f64::sqrt(2.0)).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_nqn_mut_counts_evaluations() {
+        let mut evaluations = 0;
+        let mut guess = 1.0;
+        for _i in 1..12 {
+            guess = nqn_mut(|x: f64| { evaluations += 1; sample_cubic(x) }, guess, 0.5).unwrap();
+        }
+        assert!(guess > 0.4 && guess < 0.41);
+        assert_eq!(evaluations, 33);
+    }
+
+    #[test]
+    fn test_nqn_ad() {
+        let mut guess = 1.0;
+        for _i in 1..8 {
+            guess = nqn_ad(sample_cubic_generic, guess).unwrap();
+        }
+        assert!(guess > 0.4 && guess < 0.41);
+        assert!(sample_cubic(guess).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_nqn_ad_derivative_zero() {
+        let result = nqn_ad(|x: Dual| x*x - 1.0, 0.0);
+        assert!(matches!(result, Err(NumlError::DerivativeZeroError)));
+    }
+
+    #[test]
+    fn test_derivative_f32() {
+        let result = derivative(sample_cubic_generic::<f32>, 1.0, 0.5).unwrap();
+        assert!((result - 7.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_nqn_f32() {
+        let mut guess: f32 = 1.0;
+        for _i in 1..12 {
+            guess = nqn(sample_cubic_generic, guess, 0.5).unwrap();
+        }
+        assert!(guess > 0.4 && guess < 0.41);
+        assert!(sample_cubic_generic(guess).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_nqn_ill_conditioned() {
+        // sample_cubic has a local minimum at 0, where the derivative vanishes.
+        let result = nqn(sample_cubic, 1e-10, 0.5);
+        assert!(matches!(result, Err(NumlError::IllConditioned { x, fx, .. }) if x == 1e-10 && fx == sample_cubic(1e-10)));
+        let result = nqn_with_max_step(sample_cubic, 0.05, 0.5, 1.0);
+        assert!(matches!(result, Err(NumlError::IllConditioned { .. })));
+        assert!(nqn_with_max_step(sample_cubic, 0.05, 0.5, f64::INFINITY).is_ok());
+    }
+
+    #[test]
+    fn test_nqn_non_finite_value() {
+        let result = nqn(|x: f64| 1.0/(x - 1.0), 1.0, 0.5);
+        assert!(matches!(result, Err(NumlError::NonFiniteValue { x, fx, .. }) if x == 1.0 && fx.is_infinite()));
+    }
+
+    #[test]
+    fn test_derivative_invalid_typ() {
+        let result = derivative(sample_cubic, 1.0, f64::NAN);
+        assert!(matches!(result, Err(NumlError::InvalidInput { name: "typ", value }) if value.is_nan()));
+        let result = derivative(sample_cubic, 1.0, f64::INFINITY);
+        assert!(matches!(result, Err(NumlError::InvalidInput { name: "typ", .. })));
+    }
 }
@@ -0,0 +1,578 @@
+//! Finite difference gradients, Jacobians and Hessians of functions of several variables.
+
+use crate::diff::check_typ;
+use crate::{NumlError, Real};
+
+/// Which finite difference formula to use for first derivatives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DifferenceMode {
+    /// One-sided differences (f(x+h) - f(x))/h with h = sqrt(EPS)*max(|x|, |typ|).
+    ///
+    /// The evaluation at x is shared between all components, so this is the cheapest mode, but
+    /// only about half of the available precision is achieved.
+    Forward,
+
+    /// Central differences (f(x+h) - f(x-h))/(2h) with h = cbrt(EPS)*max(|x|, |typ|).
+    ///
+    /// This costs about twice as many evaluations as Forward, but achieves about 2/3 of the
+    /// available precision, like derivative().
+    Central,
+}
+
+/// The result of a finite difference computation, together with its cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FdReport<T> {
+    /// The computed derivative.
+    pub result: T,
+
+    /// Number of evaluations of the function that were used.
+    pub evaluations: usize,
+}
+
+/// Checks typ against x and returns the step size EPS^power*max(|x[i]|, |typ[i]|) for every
+/// component, rounded so that x[i]+h[i] is exactly h[i] away from x[i].
+fn step_sizes<T: Real>(x: &[T], typ: &[T], power: f64) -> Result<Vec<T>, NumlError> {
+    if typ.len() != x.len() {
+        return Err(NumlError::DimensionMismatch);
+    }
+    for typ in typ {
+        check_typ(*typ)?;
+    }
+    let scale = T::EPSILON.powf(power);
+    Ok(x.iter().zip(typ).map(|(x, typ)| {
+        let h = scale*T::max(x.abs(), typ.abs());
+        (*x + h) - *x
+    }).collect())
+}
+
+fn first_order_power(mode: DifferenceMode) -> f64 {
+    match mode {
+        DifferenceMode::Forward => 1.0/2.0,
+        DifferenceMode::Central => 1.0/3.0,
+    }
+}
+
+/// Numerically calculates the gradient of a scalar function of several variables.
+///
+/// Inputs:
+/// - f: impl FnMut(&[T]) -> T
+/// - x: &[T]
+/// - typ: &[T]
+/// - mode: DifferenceMode
+///
+/// f() is the function whose gradient is being computed, x is the point at which it is computed,
+/// and typ holds the typical size of each component of x. The step in each component is chosen
+/// from that component and its typical value alone, exactly as derivative() does for functions of
+/// one variable. Please see the documentation of NumlError::TypError for more information on the
+/// typical value parameter.
+///
+/// DifferenceMode::Forward uses n+1 evaluations of f() and DifferenceMode::Central uses 2n, where
+/// n is the number of variables. For exact gradients at a cost independent of n, see
+/// gradient_ad().
+///
+/// A NumlError::DimensionMismatch is returned if typ and x have different lengths.
+pub fn gradient<T: Real>(mut f: impl FnMut(&[T]) -> T, x: &[T], typ: &[T], mode: DifferenceMode) -> Result<FdReport<Vec<T>>, NumlError> {
+    let h = step_sizes(x, typ, first_order_power(mode))?;
+
+    let mut point = x.to_vec();
+    let mut evaluations = 0;
+    let base = match mode {
+        DifferenceMode::Forward => {
+            evaluations += 1;
+            f(x)
+        }
+        DifferenceMode::Central => T::ZERO,
+    };
+
+    let mut result = Vec::with_capacity(x.len());
+    for i in 0..x.len() {
+        point[i] = x[i] + h[i];
+        let forward = f(&point);
+        let component = match mode {
+            DifferenceMode::Forward => {
+                evaluations += 1;
+                (forward - base)/h[i]
+            }
+            DifferenceMode::Central => {
+                point[i] = x[i] - h[i];
+                evaluations += 2;
+                (forward - f(&point))/(T::from_f64(2.0)*h[i])
+            }
+        };
+        point[i] = x[i];
+        result.push(component);
+    }
+
+    Ok(FdReport { result, evaluations })
+}
+
+/// Numerically calculates the Jacobian of a vector-valued function of several variables.
+///
+/// Inputs:
+/// - f: impl FnMut(&[T]) -> Vec<T>
+/// - x: &[T]
+/// - typ: &[T]
+/// - mode: DifferenceMode
+///
+/// The result is stored row by row, so result[i][j] is the derivative of the ith output with
+/// respect to the jth input. Step sizes and evaluation counts are the same as for gradient(): n+1
+/// evaluations in DifferenceMode::Forward and 2n in DifferenceMode::Central.
+///
+/// A NumlError::DimensionMismatch is returned if typ and x have different lengths, or if f()
+/// does not return the same number of outputs every time it is evaluated.
+pub fn jacobian<T: Real>(mut f: impl FnMut(&[T]) -> Vec<T>, x: &[T], typ: &[T], mode: DifferenceMode) -> Result<FdReport<Vec<Vec<T>>>, NumlError> {
+    let h = step_sizes(x, typ, first_order_power(mode))?;
+
+    let mut point = x.to_vec();
+    let mut evaluations = 0;
+    let base = match mode {
+        DifferenceMode::Forward => {
+            evaluations += 1;
+            Some(f(x))
+        }
+        DifferenceMode::Central => None,
+    };
+
+    let mut columns: Vec<Vec<T>> = Vec::with_capacity(x.len());
+    for i in 0..x.len() {
+        point[i] = x[i] + h[i];
+        let forward = f(&point);
+        let (backward, width) = match &base {
+            Some(base) => {
+                evaluations += 1;
+                (base.clone(), h[i])
+            }
+            None => {
+                point[i] = x[i] - h[i];
+                evaluations += 2;
+                (f(&point), T::from_f64(2.0)*h[i])
+            }
+        };
+        point[i] = x[i];
+
+        if forward.len() != backward.len() || columns.first().is_some_and(|c| c.len() != forward.len()) {
+            return Err(NumlError::DimensionMismatch);
+        }
+        columns.push(forward.iter().zip(&backward).map(|(a, b)| (*a - *b)/width).collect());
+    }
+
+    let outputs = match (&base, columns.first()) {
+        (Some(base), _) => base.len(),
+        (None, Some(column)) => column.len(),
+        (None, None) => 0,
+    };
+    let result = (0..outputs).map(|row| columns.iter().map(|column| column[row]).collect()).collect();
+
+    Ok(FdReport { result, evaluations })
+}
+
+/// Numerically calculates the Hessian of a scalar function of several variables.
+///
+/// Inputs:
+/// - f: impl FnMut(&[T]) -> T
+/// - x: &[T]
+/// - typ: &[T]
+/// - mode: DifferenceMode
+///
+/// In DifferenceMode::Forward, the entries are computed as
+/// (f(x+h[i]e[i]+h[j]e[j]) - f(x+h[i]e[i]) - f(x+h[j]e[j]) + f(x))/(h[i]h[j]) with
+/// h = cbrt(EPS)*max(|x|, |typ|), which reuses f(x) and the n evaluations at x+h[i]e[i] for every
+/// entry, for a total of 1 + n + n(n+1)/2 evaluations. The result is accurate to about 1/3 of the
+/// available precision.
+///
+/// In DifferenceMode::Central, second-order central differences with
+/// h = EPS^(1/4)*max(|x|, |typ|) are used, costing 1 + 2n^2 evaluations, and the result is
+/// accurate to about 1/2 of the available precision.
+///
+/// The returned matrix is exactly symmetric. A NumlError::DimensionMismatch is returned if typ
+/// and x have different lengths.
+pub fn hessian<T: Real>(mut f: impl FnMut(&[T]) -> T, x: &[T], typ: &[T], mode: DifferenceMode) -> Result<FdReport<Vec<Vec<T>>>, NumlError> {
+    let n = x.len();
+    let power = match mode {
+        DifferenceMode::Forward => 1.0/3.0,
+        DifferenceMode::Central => 1.0/4.0,
+    };
+    let h = step_sizes(x, typ, power)?;
+
+    let mut point = x.to_vec();
+    let mut evaluations = 1;
+    let base = f(x);
+    let mut result = vec![vec![T::ZERO; n]; n];
+
+    // Evaluates f() at x + si*h[i]*e[i] + sj*h[j]*e[j].
+    let mut eval = |i: usize, si: f64, j: usize, sj: f64| {
+        point[i] += T::from_f64(si)*h[i];
+        point[j] += T::from_f64(sj)*h[j];
+        let value = f(&point);
+        point[i] = x[i];
+        point[j] = x[j];
+        evaluations += 1;
+        value
+    };
+
+    match mode {
+        DifferenceMode::Forward => {
+            let single: Vec<T> = (0..n).map(|i| eval(i, 1.0, i, 0.0)).collect();
+            for i in 0..n {
+                for j in 0..=i {
+                    let double = if i == j { eval(i, 2.0, i, 0.0) } else { eval(i, 1.0, j, 1.0) };
+                    result[i][j] = ((double - single[i]) - (single[j] - base))/(h[i]*h[j]);
+                    result[j][i] = result[i][j];
+                }
+            }
+        }
+        DifferenceMode::Central => {
+            for i in 0..n {
+                let forward = eval(i, 1.0, i, 0.0);
+                let backward = eval(i, -1.0, i, 0.0);
+                result[i][i] = ((forward - base) + (backward - base))/(h[i]*h[i]);
+                for j in 0..i {
+                    let plus_plus = eval(i, 1.0, j, 1.0);
+                    let plus_minus = eval(i, 1.0, j, -1.0);
+                    let minus_plus = eval(i, -1.0, j, 1.0);
+                    let minus_minus = eval(i, -1.0, j, -1.0);
+                    result[i][j] = ((plus_plus - plus_minus) - (minus_plus - minus_minus))/(T::from_f64(4.0)*h[i]*h[j]);
+                    result[j][i] = result[i][j];
+                }
+            }
+        }
+    }
+
+    Ok(FdReport { result, evaluations })
+}
+
+/// A sparse matrix stored as a list of (row, column, value) triplets.
+///
+/// The triplets are sorted by row and then by column, and every position appears at most once.
+/// Positions which are not listed are zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix<T = f64> {
+    pub rows: usize,
+    pub cols: usize,
+    pub entries: Vec<(usize, usize, T)>,
+}
+
+impl<T: Real> SparseMatrix<T> {
+    /// Returns the entry at the given position, which is zero if it is not stored.
+    pub fn get(&self, row: usize, col: usize) -> T {
+        match self.entries.binary_search_by(|(i, j, _)| (*i, *j).cmp(&(row, col))) {
+            Ok(index) => self.entries[index].2,
+            Err(_) => T::ZERO,
+        }
+    }
+
+    /// Converts the matrix into a dense matrix stored row by row.
+    pub fn to_dense(&self) -> Vec<Vec<T>> {
+        let mut dense = vec![vec![T::ZERO; self.cols]; self.rows];
+        for (i, j, value) in &self.entries {
+            dense[*i][*j] = *value;
+        }
+        dense
+    }
+}
+
+/// Groups the columns of a sparse matrix so that no two columns in the same group have a nonzero
+/// in the same row.
+///
+/// Inputs:
+/// - pattern: &[(usize, usize)]
+/// - cols: usize
+///
+/// pattern lists the (row, column) positions which may be nonzero, and cols is the number of
+/// columns. The returned vector holds the group (colour) of every column, numbered from zero.
+///
+/// Columns are coloured greedily in largest-first order, that is, columns with more nonzeros are
+/// coloured first, each receiving the smallest colour not already used by a column it shares a row
+/// with. This is not guaranteed to use the fewest possible colours, but usually comes close. For
+/// example, a tridiagonal pattern always needs exactly three colours.
+///
+/// A NumlError::DimensionMismatch is returned if a position lies outside of the given number of
+/// columns.
+pub fn color_columns(pattern: &[(usize, usize)], cols: usize) -> Result<Vec<usize>, NumlError> {
+    if pattern.iter().any(|(_, j)| *j >= cols) {
+        return Err(NumlError::DimensionMismatch);
+    }
+
+    let rows = pattern.iter().map(|(i, _)| i + 1).max().unwrap_or(0);
+    let mut row_columns = vec![Vec::new(); rows];
+    let mut column_rows = vec![Vec::new(); cols];
+    for (i, j) in pattern {
+        row_columns[*i].push(*j);
+        column_rows[*j].push(*i);
+    }
+
+    let mut order: Vec<usize> = (0..cols).collect();
+    order.sort_by_key(|j| std::cmp::Reverse(column_rows[*j].len()));
+
+    let mut colors = vec![usize::MAX; cols];
+    let mut forbidden = vec![usize::MAX; cols];
+    for j in order {
+        for i in &column_rows[j] {
+            for neighbour in &row_columns[*i] {
+                if colors[*neighbour] != usize::MAX {
+                    forbidden[colors[*neighbour]] = j;
+                }
+            }
+        }
+        colors[j] = (0..cols).find(|color| forbidden[*color] != j).unwrap_or(0);
+    }
+
+    Ok(colors)
+}
+
+/// Numerically calculates a sparse Jacobian of a vector-valued function of several variables,
+/// using the column grouping of Curtis, Powell and Reid.
+///
+/// Inputs:
+/// - f: impl FnMut(&[T]) -> Vec<T>
+/// - x: &[T]
+/// - typ: &[T]
+/// - pattern: &[(usize, usize)]
+/// - mode: DifferenceMode
+///
+/// pattern lists the (row, column) positions of the Jacobian which may be nonzero; every other
+/// entry is assumed to be exactly zero. The columns are grouped with color_columns(), and all
+/// columns of a group are perturbed at once, each by its own step size chosen as in jacobian().
+/// Since the columns of a group never share a row, every nonzero entry can be read off from the
+/// change in its row.
+///
+/// This costs 1 + c evaluations of f() in DifferenceMode::Forward and 2c in
+/// DifferenceMode::Central, where c is the number of groups, instead of the n+1 or 2n of
+/// jacobian(). For banded Jacobians c is independent of n.
+///
+/// A NumlError::DimensionMismatch is returned if typ and x have different lengths, if the
+/// pattern refers to rows or columns which do not exist, or if f() does not return the same
+/// number of outputs every time it is evaluated.
+pub fn sparse_jacobian<T: Real>(mut f: impl FnMut(&[T]) -> Vec<T>, x: &[T], typ: &[T], pattern: &[(usize, usize)], mode: DifferenceMode) -> Result<FdReport<SparseMatrix<T>>, NumlError> {
+    let h = step_sizes(x, typ, first_order_power(mode))?;
+    let colors = color_columns(pattern, x.len())?;
+    let groups = colors.iter().map(|c| c + 1).max().unwrap_or(0);
+
+    let mut positions = pattern.to_vec();
+    positions.sort_unstable();
+    positions.dedup();
+
+    let mut evaluations = 0;
+    let base = match mode {
+        DifferenceMode::Forward => {
+            evaluations += 1;
+            Some(f(x))
+        }
+        DifferenceMode::Central => None,
+    };
+
+    let mut rows = base.as_ref().map(|base| base.len());
+    let mut values = vec![T::ZERO; positions.len()];
+    let mut point = x.to_vec();
+    for group in 0..groups {
+        let perturb = |point: &mut Vec<T>, sign: f64| {
+            for j in 0..x.len() {
+                point[j] = if colors[j] == group { x[j] + T::from_f64(sign)*h[j] } else { x[j] };
+            }
+        };
+
+        perturb(&mut point, 1.0);
+        let forward = f(&point);
+        let (backward, factor) = match &base {
+            Some(base) => {
+                evaluations += 1;
+                (base.clone(), T::ONE)
+            }
+            None => {
+                perturb(&mut point, -1.0);
+                evaluations += 2;
+                (f(&point), T::from_f64(2.0))
+            }
+        };
+
+        let outputs = *rows.get_or_insert(forward.len());
+        if forward.len() != outputs || backward.len() != outputs {
+            return Err(NumlError::DimensionMismatch);
+        }
+
+        for ((i, j), value) in positions.iter().zip(values.iter_mut()) {
+            if colors[*j] == group {
+                if *i >= outputs {
+                    return Err(NumlError::DimensionMismatch);
+                }
+                *value = (forward[*i] - backward[*i])/(factor*h[*j]);
+            }
+        }
+    }
+
+    let rows = rows.unwrap_or(0);
+    if positions.iter().any(|(i, _)| *i >= rows) {
+        return Err(NumlError::DimensionMismatch);
+    }
+    let entries = positions.iter().zip(values).map(|((i, j), value)| (*i, *j, value)).collect();
+
+    Ok(FdReport { result: SparseMatrix { rows, cols: x.len(), entries }, evaluations })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rosenbrock(x: &[f64]) -> f64 {
+        100.0*(x[1] - x[0]*x[0]).powi(2) + (1.0 - x[0]).powi(2)
+    }
+
+    fn rosenbrock_gradient(x: &[f64]) -> Vec<f64> {
+        vec![-400.0*x[0]*(x[1] - x[0]*x[0]) - 2.0*(1.0 - x[0]), 200.0*(x[1] - x[0]*x[0])]
+    }
+
+    fn rosenbrock_hessian(x: &[f64]) -> Vec<Vec<f64>> {
+        vec![
+            vec![1200.0*x[0]*x[0] - 400.0*x[1] + 2.0, -400.0*x[0]],
+            vec![-400.0*x[0], 200.0],
+        ]
+    }
+
+    fn max_error(a: &[Vec<f64>], b: &[Vec<f64>]) -> f64 {
+        a.iter().flatten().zip(b.iter().flatten()).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_gradient() {
+        let x = [1.2, 1.0];
+        let exact = rosenbrock_gradient(&x);
+
+        let forward = gradient(rosenbrock, &x, &[1.0, 1.0], DifferenceMode::Forward).unwrap();
+        assert_eq!(forward.evaluations, 3);
+        assert!(max_error(&[forward.result], std::slice::from_ref(&exact)) < 1e-4);
+
+        let central = gradient(rosenbrock, &x, &[1.0, 1.0], DifferenceMode::Central).unwrap();
+        assert_eq!(central.evaluations, 4);
+        assert!(max_error(&[central.result], &[exact]) < 1e-7);
+    }
+
+    #[test]
+    fn test_gradient_counts_match_closure() {
+        let mut evaluations = 0;
+        let report = gradient(|x: &[f64]| { evaluations += 1; x.iter().map(|x| x*x).sum() }, &[1.0; 5], &[1.0; 5], DifferenceMode::Central).unwrap();
+        assert_eq!(report.evaluations, evaluations);
+        assert_eq!(report.evaluations, 10);
+        assert!(report.result.iter().all(|g| (g - 2.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_jacobian() {
+        let f = |x: &[f64]| vec![x[0]*x[1], x[1].sin() + x[2], (x[0]*x[2]).exp()];
+        let x: [f64; 3] = [0.5, 1.0, -0.3];
+        let exact = vec![
+            vec![x[1], x[0], 0.0],
+            vec![0.0, x[1].cos(), 1.0],
+            vec![x[2]*(x[0]*x[2]).exp(), 0.0, x[0]*(x[0]*x[2]).exp()],
+        ];
+
+        let forward = jacobian(f, &x, &[1.0; 3], DifferenceMode::Forward).unwrap();
+        assert_eq!(forward.evaluations, 4);
+        assert!(max_error(&forward.result, &exact) < 1e-7);
+
+        let central = jacobian(f, &x, &[1.0; 3], DifferenceMode::Central).unwrap();
+        assert_eq!(central.evaluations, 6);
+        assert!(max_error(&central.result, &exact) < 1e-9);
+    }
+
+    #[test]
+    fn test_jacobian_non_square() {
+        let f = |x: &[f64]| vec![x[0] + 2.0*x[1]];
+        let report = jacobian(f, &[1.0, 1.0], &[1.0, 1.0], DifferenceMode::Central).unwrap();
+        assert_eq!(report.result.len(), 1);
+        assert!(max_error(&report.result, &[vec![1.0, 2.0]]) < 1e-10);
+    }
+
+    #[test]
+    fn test_hessian() {
+        let x = [1.2, 1.0];
+        let exact = rosenbrock_hessian(&x);
+
+        let forward = hessian(rosenbrock, &x, &[1.0, 1.0], DifferenceMode::Forward).unwrap();
+        assert_eq!(forward.evaluations, 1 + 2 + 3);
+        assert!(max_error(&forward.result, &exact) < 1e-1);
+        assert_eq!(forward.result[0][1], forward.result[1][0]);
+
+        let central = hessian(rosenbrock, &x, &[1.0, 1.0], DifferenceMode::Central).unwrap();
+        assert_eq!(central.evaluations, 1 + 2*4);
+        assert!(max_error(&central.result, &exact) < 1e-4);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(gradient(rosenbrock, &[1.0, 1.0], &[1.0], DifferenceMode::Forward), Err(NumlError::DimensionMismatch)));
+        assert!(matches!(hessian(rosenbrock, &[1.0, 1.0], &[1.0, 0.0], DifferenceMode::Forward), Err(NumlError::TypError)));
+
+        let mut calls = 0;
+        let changing = |x: &[f64]| { calls += 1; vec![x[0]; calls] };
+        assert!(matches!(jacobian(changing, &[1.0, 1.0], &[1.0, 1.0], DifferenceMode::Forward), Err(NumlError::DimensionMismatch)));
+    }
+
+    fn tridiagonal_pattern(n: usize) -> Vec<(usize, usize)> {
+        (0..n).flat_map(|i| (i.saturating_sub(1)..usize::min(n, i + 2)).map(move |j| (i, j))).collect()
+    }
+
+    /// A discretised nonlinear boundary value problem with a tridiagonal Jacobian.
+    fn bratu(x: &[f64]) -> Vec<f64> {
+        let n = x.len();
+        (0..n).map(|i| {
+            let left = if i > 0 { x[i-1] } else { 0.0 };
+            let right = if i + 1 < n { x[i+1] } else { 0.0 };
+            left - 2.0*x[i] + right + 0.01*x[i].exp()
+        }).collect()
+    }
+
+    #[test]
+    fn test_color_columns() {
+        let pattern = tridiagonal_pattern(20);
+        let colors = color_columns(&pattern, 20).unwrap();
+        assert_eq!(colors.iter().max(), Some(&2));
+        for (i, j) in &pattern {
+            for (k, l) in &pattern {
+                if i == k && j != l {
+                    assert_ne!(colors[*j], colors[*l]);
+                }
+            }
+        }
+
+        // A dense row forces every column into its own group.
+        let dense_row: Vec<(usize, usize)> = (0..5).map(|j| (0, j)).collect();
+        let mut colors = color_columns(&dense_row, 5).unwrap();
+        colors.sort();
+        assert_eq!(colors, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_sparse_jacobian() {
+        let n = 50;
+        let x: Vec<f64> = (0..n).map(|i| (i as f64/n as f64).sin()).collect();
+        let typ = vec![1.0; n];
+        let pattern = tridiagonal_pattern(n);
+
+        for mode in [DifferenceMode::Forward, DifferenceMode::Central] {
+            let sparse = sparse_jacobian(bratu, &x, &typ, &pattern, mode).unwrap();
+            let dense = jacobian(bratu, &x, &typ, mode).unwrap();
+            assert_eq!(sparse.result.entries.len(), pattern.len());
+            assert!(max_error(&sparse.result.to_dense(), &dense.result) < 1e-6);
+            let expected = match mode {
+                DifferenceMode::Forward => 4,
+                DifferenceMode::Central => 6,
+            };
+            assert_eq!(sparse.evaluations, expected);
+        }
+
+        let sparse = sparse_jacobian(bratu, &x, &typ, &pattern, DifferenceMode::Central).unwrap();
+        assert!((sparse.result.get(3, 3) - (-2.0 + 0.01*x[3].exp())).abs() < 1e-9);
+        assert!((sparse.result.get(3, 4) - 1.0).abs() < 1e-9);
+        assert_eq!(sparse.result.get(3, 10), 0.0);
+    }
+
+    #[test]
+    fn test_sparse_jacobian_errors() {
+        let pattern = [(0, 0), (5, 1)];
+        let result = sparse_jacobian(bratu, &[1.0, 1.0], &[1.0, 1.0], &pattern, DifferenceMode::Forward);
+        assert!(matches!(result, Err(NumlError::DimensionMismatch)));
+        let pattern = [(0, 0), (1, 2)];
+        let result = sparse_jacobian(bratu, &[1.0, 1.0], &[1.0, 1.0], &pattern, DifferenceMode::Forward);
+        assert!(matches!(result, Err(NumlError::DimensionMismatch)));
+    }
+}
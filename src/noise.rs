@@ -0,0 +1,238 @@
+//! Noise estimation and noise-aware differentiation for functions which are only accurate to
+//! well above machine precision, such as the results of simulations.
+
+use crate::diff::check_typ;
+use crate::{NumlError, Real};
+
+/// A derivative of a noisy function, together with the quantities used to compute it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoisyDerivative<T = f64> {
+    /// The computed derivative.
+    pub derivative: T,
+
+    /// Expected absolute error of the derivative, combining the effect of the noise and the
+    /// truncation error of the difference formula.
+    pub error: T,
+
+    /// The noise level of f() that was used, either as supplied or as estimated.
+    pub noise: T,
+
+    /// The step size that was used.
+    pub step: T,
+
+    /// Number of evaluations of f() that were used.
+    pub evaluations: usize,
+}
+
+/// Estimates the noise level of a function near the specified point, using the difference table
+/// method (ECnoise) of Moré and Wild.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - typ: T
+///
+/// f() is evaluated at the nine points x + k*d for k = -4, ..., 4, with d = 1e-4*max(|x|, |typ|).
+/// For a smooth function the higher differences of these values shrink rapidly, until they are
+/// dominated by the noise, whose contribution to the kth difference has a known size. The
+/// returned value is an estimate of the standard deviation of the noise in f() near x. Please see
+/// the documentation of NumlError::TypError for more information on the typical value parameter.
+///
+/// A NumlError::NoiseEstimationError is returned if all nine values are equal, which means that
+/// d is too small compared to the resolution of f() and typ should be increased, or if the
+/// function varies too much over the nine points to see the noise, which means that typ should be
+/// decreased.
+pub fn estimate_noise<T: Real>(mut f: impl FnMut(T) -> T, x: T, typ: T) -> Result<T, NumlError> {
+    let d = noise_step(x, typ)?;
+    let mut values = [T::ZERO; 9];
+    for (k, value) in values.iter_mut().enumerate() {
+        *value = f(x + T::from_f64(k as f64 - 4.0)*d);
+    }
+    noise_from_table(values)
+}
+
+fn noise_step<T: Real>(x: T, typ: T) -> Result<T, NumlError> {
+    check_typ(typ)?;
+    let d = T::from_f64(1e-4)*T::max(x.abs(), typ.abs());
+    Ok((x + d) - x)
+}
+
+/// The core of ECnoise: estimates the noise level from nine equally spaced function values.
+fn noise_from_table<T: Real>(mut values: [T; 9]) -> Result<T, NumlError> {
+    const M: usize = 8;
+
+    let fmin = values.iter().copied().fold(T::INFINITY, T::min);
+    let fmax = values.iter().copied().fold(T::NEG_INFINITY, T::max);
+    if !(fmin.is_finite() && fmax.is_finite()) || (fmax - fmin) > T::from_f64(0.1)*T::max(fmax.abs(), fmin.abs()) {
+        return Err(NumlError::NoiseEstimationError);
+    }
+
+    let mut levels = [T::ZERO; M];
+    let mut sign_changes = [false; M];
+    let mut gamma = T::ONE;
+    for j in 1..=M {
+        for i in 0..=M-j {
+            values[i] = values[i+1] - values[i];
+        }
+        let differences = &values[..=M-j];
+        if j == 1 && differences.iter().all(|d| *d == T::ZERO) {
+            return Err(NumlError::NoiseEstimationError);
+        }
+
+        // For independent noise of variance s^2, the jth difference has variance
+        // (2j)!/(j!)^2 * s^2.
+        gamma *= T::from_f64(0.5*(j as f64/(2*j - 1) as f64));
+        let mean_square = differences.iter().fold(T::ZERO, |sum, d| sum + *d**d)/T::from_f64(differences.len() as f64);
+        levels[j-1] = (gamma*mean_square).sqrt();
+
+        let low = differences.iter().copied().fold(T::INFINITY, T::min);
+        let high = differences.iter().copied().fold(T::NEG_INFINITY, T::max);
+        sign_changes[j-1] = low*high < T::ZERO;
+    }
+
+    // The noise level is taken from the first order at which three consecutive estimates agree
+    // and the differences change sign, as the smooth part of f() would not.
+    for k in 0..M-2 {
+        let low = levels[k..k+3].iter().copied().fold(T::INFINITY, T::min);
+        let high = levels[k..k+3].iter().copied().fold(T::NEG_INFINITY, T::max);
+        if high <= T::from_f64(4.0)*low && sign_changes[k] {
+            return Ok(levels[k]);
+        }
+    }
+
+    Err(NumlError::NoiseEstimationError)
+}
+
+/// Numerically calculates the derivative of a noisy function at the specified point, choosing the
+/// step size according to the noise level.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x: T
+/// - typ: T
+/// - noise: Option<T>
+///
+/// derivative() assumes that f() is accurate to machine precision, and its step size is far too
+/// small for functions that are only accurate to, say, 1e-8. Here noise is the absolute noise
+/// level of f() near x (the size of its random errors); if it is None, it is estimated with
+/// estimate_noise(), which costs eight extra evaluations.
+///
+/// A central difference f'(x) ~= (f(x+h) - f(x-h))/(2h) has error roughly noise/h + M*h^2/6,
+/// where M is the size of the third derivative, which is minimised by h = (3*noise/M)^(1/3). M is
+/// estimated from a third difference with a wider step; if that difference is lost in the noise,
+/// M is assumed to be |f(x)|/max(|x|, |typ|)^3, which gives the same typical-value rule as
+/// derivative() with the relative noise level in place of EPS. The returned error is the value of
+/// the error model at the chosen step.
+///
+/// With a supplied noise level this uses 7 evaluations of f(), and 15 if the noise level has to be
+/// estimated.
+pub fn derivative_noisy<T: Real>(mut f: impl FnMut(T) -> T, x: T, typ: T, noise: Option<T>) -> Result<NoisyDerivative<T>, NumlError> {
+    let d = noise_step(x, typ)?;
+    let scale = T::max(x.abs(), typ.abs());
+
+    let mut evaluations = 0;
+    let mut eval = |t: T| {
+        evaluations += 1;
+        f(t)
+    };
+
+    let fx = eval(x);
+    let noise = match noise {
+        Some(noise) => noise.abs(),
+        None => {
+            let mut values = [T::ZERO; 9];
+            for (k, value) in values.iter_mut().enumerate() {
+                *value = if k == 4 { fx } else { eval(x + T::from_f64(k as f64 - 4.0)*d) };
+            }
+            noise_from_table(values)?
+        }
+    };
+    // f() cannot be more accurate than its own rounding.
+    let noise = T::max(noise, T::EPSILON*fx.abs()).max(T::MIN_POSITIVE);
+
+    // Pilot step from the typical-value rule, which assumes that f() changes by about |f(x)|
+    // when x changes by about max(|x|, |typ|).
+    let two = T::from_f64(2.0);
+    let three = T::from_f64(3.0);
+    let relative_noise = noise/T::max(fx.abs(), noise);
+    let pilot = T::from_f64(4.0)*(three*relative_noise).cbrt()*scale;
+    let pilot = (x + pilot) - x;
+
+    let third_difference = eval(x + two*pilot) - two*eval(x + pilot) + two*eval(x - pilot) - eval(x - two*pilot);
+    let third_derivative = if third_difference.abs() >= T::from_f64(100.0)*noise {
+        (third_difference/(two*pilot.powi(3))).abs()
+    } else {
+        T::max(fx.abs(), noise)/scale.powi(3)
+    };
+
+    let h = (three*noise/third_derivative).cbrt();
+    let h = (x + h) - x;
+    let derivative = (eval(x + h) - eval(x - h))/(two*h);
+    let error = noise/h + third_derivative*h*h/T::from_f64(6.0);
+
+    Ok(NoisyDerivative { derivative, error, noise, step: h, evaluations })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derivative_mut;
+
+    /// Returns sin(x) plus deterministic pseudo-random noise uniformly distributed in
+    /// [-amplitude, amplitude].
+    fn noisy_sin(amplitude: f64) -> impl FnMut(f64) -> f64 {
+        let mut state: u64 = 0x853c49e6748fea9b;
+        move |x: f64| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let uniform = (state >> 11) as f64/(1u64 << 53) as f64;
+            x.sin() + amplitude*(2.0*uniform - 1.0)
+        }
+    }
+
+    #[test]
+    fn test_estimate_noise() {
+        // Uniform noise on [-a, a] has standard deviation a/sqrt(3).
+        let amplitude = 1e-8;
+        let expected = amplitude/f64::sqrt(3.0);
+        let estimate = estimate_noise(noisy_sin(amplitude), 1.0, 1.0).unwrap();
+        assert!(estimate > expected/3.0 && estimate < 3.0*expected);
+    }
+
+    #[test]
+    fn test_estimate_noise_errors() {
+        let constant = estimate_noise(|_x: f64| 1.0, 1.0, 1.0);
+        assert!(matches!(constant, Err(NumlError::NoiseEstimationError)));
+        let steep = estimate_noise(|x: f64| (1e6*x).exp(), 1.0, 1.0);
+        assert!(matches!(steep, Err(NumlError::NoiseEstimationError)));
+    }
+
+    #[test]
+    fn test_derivative_noisy_estimated() {
+        let x: f64 = 1.0;
+        let exact = x.cos();
+        let noisy = derivative_noisy(noisy_sin(1e-8), x, 1.0, None).unwrap();
+        let naive = derivative_mut(noisy_sin(1e-8), x, 1.0).unwrap();
+        assert!((noisy.derivative - exact).abs() < 1e-4);
+        assert!((noisy.derivative - exact).abs() < 3.0*noisy.error);
+        assert!(noisy.error < 1e-4);
+        assert!((noisy.derivative - exact).abs() < (naive - exact).abs());
+        assert_eq!(noisy.evaluations, 15);
+    }
+
+    #[test]
+    fn test_derivative_noisy_supplied() {
+        let x: f64 = 0.3;
+        let noisy = derivative_noisy(noisy_sin(1e-6), x, 1.0, Some(1e-6)).unwrap();
+        assert_eq!(noisy.noise, 1e-6);
+        assert_eq!(noisy.evaluations, 7);
+        assert!((noisy.derivative - x.cos()).abs() < 3.0*noisy.error);
+        assert!(noisy.step > 1e-3 && noisy.step < 1e-1);
+    }
+
+    #[test]
+    fn test_derivative_noisy_smooth_function() {
+        // Without noise this should do about as well as derivative().
+        let noisy = derivative_noisy(f64::exp, 1.0, 1.0, Some(0.0)).unwrap();
+        assert!((noisy.derivative - f64::exp(1.0)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,561 @@
+//! Polynomials with accurate evaluation, and finding all of their roots.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::{Complex, NumlError, Real};
+
+/// A polynomial c[0] + c[1]*x + ... + c[n]*x^n with real coefficients c = coefficients.
+///
+/// The coefficients are stored in order of increasing power. Trailing zero coefficients are
+/// allowed and ignored, also by ==, and the operations below never produce them; the zero
+/// polynomial has no coefficients. Polynomials support addition, subtraction and multiplication
+/// with each other, both by value and by reference, and multiplication by a plain real number.
+#[derive(Debug, Clone, Default)]
+pub struct Polynomial<T = f64> {
+    pub coefficients: Vec<T>,
+}
+
+impl<T: Real> Polynomial<T> {
+    /// Creates the polynomial with the given coefficients, in order of increasing power, dropping
+    /// trailing zeros.
+    pub fn new(mut coefficients: Vec<T>) -> Self {
+        while coefficients.last() == Some(&T::ZERO) {
+            coefficients.pop();
+        }
+        Polynomial { coefficients }
+    }
+
+    /// Creates the monic polynomial (x - r_1)*...*(x - r_n) with the given roots.
+    pub fn from_roots(roots: &[T]) -> Self {
+        let mut coefficients = vec![T::ONE];
+        for &root in roots {
+            coefficients.insert(0, T::ZERO);
+            for k in 0..coefficients.len() - 1 {
+                let shifted = coefficients[k + 1];
+                coefficients[k] -= root*shifted;
+            }
+        }
+        Polynomial::new(coefficients)
+    }
+
+    /// The coefficients without trailing zeros.
+    fn trimmed(&self) -> &[T] {
+        let len = self.coefficients.iter().rposition(|&c| c != T::ZERO).map_or(0, |k| k + 1);
+        &self.coefficients[..len]
+    }
+
+    /// Returns true if all coefficients are zero.
+    pub fn is_zero(&self) -> bool {
+        self.trimmed().is_empty()
+    }
+
+    /// The degree, which is zero for constants including the zero polynomial.
+    pub fn degree(&self) -> usize {
+        self.trimmed().len().saturating_sub(1)
+    }
+
+    /// Evaluates the polynomial at x with Horner's rule.
+    pub fn eval(&self, x: T) -> T {
+        self.trimmed().iter().rev().fold(T::ZERO, |y, &c| y*x + c)
+    }
+
+    /// Evaluates the polynomial at a complex z with Horner's rule.
+    pub fn eval_complex(&self, z: Complex<T>) -> Complex<T> {
+        self.trimmed().iter().rev().fold(Complex::default(), |y, &c| y*z + c)
+    }
+
+    /// Evaluates the polynomial at x with Horner's rule, and returns the value along with a bound
+    /// on its rounding error.
+    ///
+    /// The bound is Higham's running error bound, computed alongside the value at the cost of two
+    /// extra operations per coefficient. It is usually much sharper than the a priori bound
+    /// n*EPS*sum(|c[k]|*|x|^k), and tells whether the computed value can be trusted: near a root
+    /// of a polynomial with large coefficients the error can exceed the value itself.
+    pub fn eval_with_error(&self, x: T) -> (T, T) {
+        let coefficients = self.trimmed();
+        let Some((&leading, rest)) = coefficients.split_last() else {
+            return (T::ZERO, T::ZERO);
+        };
+        let mut y = leading;
+        let mut mu = T::from_f64(0.5)*y.abs();
+        for &c in rest.iter().rev() {
+            y = y*x + c;
+            mu = mu*x.abs() + y.abs();
+        }
+        let unit_roundoff = T::from_f64(0.5)*T::EPSILON;
+        (y, unit_roundoff*(T::from_f64(2.0)*mu - y.abs()).max(T::ZERO))
+    }
+
+    /// Evaluates the polynomial at x with the compensated Horner scheme.
+    ///
+    /// The rounding error of every multiplication and addition of Horner's rule is computed exactly
+    /// with error-free transformations, and the polynomial formed by these errors is evaluated
+    /// alongside to correct the result. The value is as accurate as if Horner's rule had been run
+    /// in twice the working precision and then rounded, so its relative error is about
+    /// EPS + cond*EPS^2, where cond = sum(|c[k]|*|x|^k)/|p(x)| is the condition number of the
+    /// evaluation, instead of cond*EPS. This makes the value usable near multiple or clustered
+    /// roots, where cond is huge. It costs about ten times as much as eval(). The products are
+    /// split with Dekker's algorithm, which overflows for intermediate values within a factor of
+    /// about 1/sqrt(EPS) of the largest finite number.
+    pub fn eval_compensated(&self, x: T) -> T {
+        let coefficients = self.trimmed();
+        let Some((&leading, rest)) = coefficients.split_last() else {
+            return T::ZERO;
+        };
+        let mut s = leading;
+        let mut correction = T::ZERO;
+        for &c in rest.iter().rev() {
+            let (product, product_error) = two_product(s, x);
+            let (sum, sum_error) = two_sum(product, c);
+            s = sum;
+            correction = correction*x + (product_error + sum_error);
+        }
+        s + correction
+    }
+
+    /// Returns the derivative.
+    pub fn derivative(&self) -> Self {
+        let coefficients = self.trimmed();
+        Polynomial::new(coefficients.iter().enumerate().skip(1).map(|(k, &c)| T::from_f64(k as f64)*c).collect())
+    }
+
+    /// Returns the antiderivative whose constant term is zero.
+    pub fn integral(&self) -> Self {
+        let coefficients = self.trimmed();
+        if coefficients.is_empty() {
+            return Polynomial::default();
+        }
+        let mut integral = vec![T::ZERO];
+        integral.extend(coefficients.iter().enumerate().map(|(k, &c)| c/T::from_f64((k + 1) as f64)));
+        Polynomial::new(integral)
+    }
+
+    /// Divides by another polynomial, returning the quotient q and remainder r with
+    /// self = q*divisor + r and r of lower degree than divisor.
+    ///
+    /// A NumlError::InvalidInput is returned if divisor is the zero polynomial.
+    pub fn div_rem(&self, divisor: &Self) -> Result<(Self, Self), NumlError> {
+        let d = divisor.trimmed();
+        let Some(&lead) = d.last() else {
+            return Err(NumlError::InvalidInput { name: "divisor", value: 0.0 });
+        };
+        let n = self.trimmed();
+        if n.len() < d.len() {
+            return Ok((Polynomial::default(), Polynomial::new(n.to_vec())));
+        }
+
+        let mut remainder = n.to_vec();
+        let mut quotient = vec![T::ZERO; n.len() - d.len() + 1];
+        for k in (0..quotient.len()).rev() {
+            let coefficient = remainder[k + d.len() - 1]/lead;
+            quotient[k] = coefficient;
+            for (j, &dj) in d.iter().enumerate() {
+                remainder[k + j] -= coefficient*dj;
+            }
+        }
+        remainder.truncate(d.len() - 1);
+        Ok((Polynomial::new(quotient), Polynomial::new(remainder)))
+    }
+
+    /// Divides out the factor (x - root) by synthetic division, returning the quotient and the
+    /// remainder, which is the value of the polynomial at root.
+    ///
+    /// The division runs from the leading coefficient down, which is numerically stable when root
+    /// is one of the smallest roots in magnitude, so deflating roots in increasing order of
+    /// magnitude keeps the quotients accurate.
+    pub fn deflate(&self, root: T) -> (Self, T) {
+        let coefficients = self.trimmed();
+        let Some((&leading, rest)) = coefficients.split_last() else {
+            return (Polynomial::default(), T::ZERO);
+        };
+        let mut quotient = vec![T::ZERO; rest.len()];
+        let mut carry = leading;
+        for (k, &c) in rest.iter().enumerate().rev() {
+            quotient[k] = carry;
+            carry = c + root*carry;
+        }
+        (Polynomial::new(quotient), carry)
+    }
+}
+
+impl<T: Real> PartialEq for Polynomial<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed() == other.trimmed()
+    }
+}
+
+/// Computes a + b and its rounding error exactly, with Knuth's TwoSum.
+fn two_sum<T: Real>(a: T, b: T) -> (T, T) {
+    let sum = a + b;
+    let b_virtual = sum - a;
+    (sum, (a - (sum - b_virtual)) + (b - b_virtual))
+}
+
+/// Splits a into a high and a low part with at most half the mantissa bits each, so that their
+/// products are exact.
+fn split<T: Real>(a: T) -> (T, T) {
+    let digits = 1.0 - T::EPSILON.value().log2();
+    let factor = T::from_f64(2f64.powi((digits/2.0).ceil() as i32) + 1.0);
+    let c = factor*a;
+    let high = c - (c - a);
+    (high, a - high)
+}
+
+/// Computes a*b and its rounding error exactly, with Dekker's TwoProduct.
+fn two_product<T: Real>(a: T, b: T) -> (T, T) {
+    let product = a*b;
+    let (a_high, a_low) = split(a);
+    let (b_high, b_low) = split(b);
+    (product, a_low*b_low - (((product - a_high*b_high) - a_low*b_high) - a_high*b_low))
+}
+
+impl<T: Real> Neg for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn neg(self) -> Polynomial<T> {
+        Polynomial::new(self.trimmed().iter().map(|&c| -c).collect())
+    }
+}
+
+impl<T: Real> Add for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn add(self, rhs: Self) -> Polynomial<T> {
+        let (a, b) = (self.trimmed(), rhs.trimmed());
+        let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+        let mut sum = long.to_vec();
+        for (s, &c) in sum.iter_mut().zip(short) {
+            *s += c;
+        }
+        Polynomial::new(sum)
+    }
+}
+
+impl<T: Real> Sub for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn sub(self, rhs: Self) -> Polynomial<T> {
+        self + &(-rhs)
+    }
+}
+
+impl<T: Real> Mul for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn mul(self, rhs: Self) -> Polynomial<T> {
+        let (a, b) = (self.trimmed(), rhs.trimmed());
+        if a.is_empty() || b.is_empty() {
+            return Polynomial::default();
+        }
+        let mut product = vec![T::ZERO; a.len() + b.len() - 1];
+        for (i, &ai) in a.iter().enumerate() {
+            for (j, &bj) in b.iter().enumerate() {
+                product[i + j] += ai*bj;
+            }
+        }
+        Polynomial::new(product)
+    }
+}
+
+impl<T: Real> Mul<T> for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn mul(self, rhs: T) -> Polynomial<T> {
+        Polynomial::new(self.trimmed().iter().map(|&c| c*rhs).collect())
+    }
+}
+
+impl<T: Real> Neg for Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn neg(self) -> Polynomial<T> {
+        -&self
+    }
+}
+
+impl<T: Real> Mul<T> for Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn mul(self, rhs: T) -> Polynomial<T> {
+        &self*rhs
+    }
+}
+
+/// Implements the binary operators between owned polynomials in terms of those between
+/// references.
+macro_rules! impl_owned_ops {
+    ($($trait:ident $method:ident),*) => {$(
+        impl<T: Real> $trait for Polynomial<T> {
+            type Output = Polynomial<T>;
+
+            fn $method(self, rhs: Self) -> Polynomial<T> {
+                (&self).$method(&rhs)
+            }
+        }
+    )*};
+}
+
+impl_owned_ops!(Add add, Sub sub, Mul mul);
+
+/// A root of a polynomial found by polynomial_roots().
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolynomialRoot<T = f64> {
+    /// The computed root.
+    pub root: Complex<T>,
+
+    /// Radius of a disk around root which contains a root of the polynomial (see
+    /// polynomial_roots()).
+    pub error: T,
+
+    /// |p(root)|.
+    pub residual: T,
+
+    /// Whether the iteration for this root met its stopping criterion.
+    pub converged: bool,
+}
+
+/// Finds all complex roots of a polynomial with the Aberth-Ehrlich method.
+///
+/// Inputs:
+/// - p: &Polynomial<T>
+/// - max_iterations: usize
+///
+/// All n roots of a polynomial of degree n are approximated simultaneously. Each iteration
+/// updates every approximation z_i by w_i = N_i/(1 - N_i*sum(1/(z_i - z_j), j != i)), where
+/// N_i = p(z_i)/p'(z_i) is the Newton correction: the sum keeps the approximations apart, so that
+/// each converges to a different root, cubically for simple roots. The iterations start from
+/// points spread around a circle whose radius is the geometric mean of the moduli of the roots,
+/// and an approximation stops being updated once |p(z_i)| is below the a priori bound
+/// 4*n*EPS*sum(|c[k]|*|z_i|^k) on the rounding error of evaluating p(z_i), since then it is as good
+/// as the working precision allows. Roots at zero, from leading zero coefficients, are split off
+/// exactly first.
+///
+/// Each returned root comes with the radius n*(|p(z_i)| + bound)/|c[n]*prod(z_i - z_j, j != i)|
+/// of a disk around it. The union of these disks contains all roots of p, and each connected
+/// component of it formed by m disks contains exactly m roots, so the radius is a reliable error
+/// estimate for a well separated root. For a root of multiplicity m, the computed approximations
+/// are only accurate to about EPS^(1/m), and their disks overlap. The roots are sorted by real part
+/// and then by imaginary part. Approximations which did not converge within max_iterations
+/// iterations are returned with converged set to false.
+///
+/// A NumlError::InvalidInput is returned if p is the zero polynomial, which has every number as a
+/// root, or if any of its coefficients is not finite.
+pub fn polynomial_roots<T: Real>(p: &Polynomial<T>, max_iterations: usize) -> Result<Vec<PolynomialRoot<T>>, NumlError> {
+    let coefficients = p.trimmed();
+    if let Some(&c) = coefficients.iter().find(|c| !c.is_finite()) {
+        return Err(NumlError::InvalidInput { name: "coefficients", value: c.value() });
+    }
+    if coefficients.is_empty() {
+        return Err(NumlError::InvalidInput { name: "coefficients", value: 0.0 });
+    }
+
+    let zeros = coefficients.iter().take_while(|&&c| c == T::ZERO).count();
+    let c = &coefficients[zeros..];
+    let n = c.len() - 1;
+    let mut roots = vec![PolynomialRoot { root: Complex::default(), error: T::ZERO, residual: T::ZERO, converged: true }; zeros];
+    if n == 0 {
+        return Ok(roots);
+    }
+
+    let radius = (c[0].abs()/c[n].abs()).powf(1.0/n as f64);
+    let mut z: Vec<Complex<T>> = (0..n).map(|k| {
+        Complex::from_polar(radius, T::from_f64(2.0)*T::PI*T::from_f64(k as f64/n as f64) + T::from_f64(0.4))
+    }).collect();
+    let mut converged = vec![false; n];
+    let one = Complex::new(T::ONE, T::ZERO);
+
+    for _ in 0..max_iterations {
+        if converged.iter().all(|&done| done) {
+            break;
+        }
+        for i in 0..n {
+            if converged[i] {
+                continue;
+            }
+            let (value, slope, bound) = eval_with_derivative(c, z[i]);
+            if value.abs() <= bound {
+                converged[i] = true;
+                continue;
+            }
+            let newton = value/slope;
+            let repulsion = (0..n).filter(|&j| j != i).fold(Complex::default(), |sum, j| sum + (z[i] - z[j]).recip());
+            let w = newton/(one - newton*repulsion);
+            if !w.is_finite() {
+                // p'(z_i) vanished or two approximations coincide: nudge z_i off the spot.
+                z[i] = z[i]*Complex::from_polar(T::ONE + T::EPSILON.sqrt(), T::EPSILON.sqrt()) + T::EPSILON.sqrt();
+                continue;
+            }
+            z[i] -= w;
+            if w.abs() <= T::EPSILON*z[i].abs() {
+                converged[i] = true;
+            }
+        }
+    }
+
+    let n_real = T::from_f64(n as f64);
+    for i in 0..n {
+        let (value, _, bound) = eval_with_derivative(c, z[i]);
+        let product = (0..n).filter(|&j| j != i).fold(T::ONE, |product, j| product*(z[i] - z[j]).abs());
+        let error = n_real*(value.abs() + bound)/(c[n].abs()*product);
+        roots.push(PolynomialRoot {
+            root: z[i],
+            error: if error.is_nan() { T::INFINITY } else { error },
+            residual: value.abs(),
+            converged: converged[i],
+        });
+    }
+    roots.sort_by(|a, b| (a.root.re, a.root.im).partial_cmp(&(b.root.re, b.root.im)).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(roots)
+}
+
+/// Evaluates p(z) and p'(z) with Horner's rule, along with an a priori bound on the rounding error
+/// of p(z).
+fn eval_with_derivative<T: Real>(c: &[T], z: Complex<T>) -> (Complex<T>, Complex<T>, T) {
+    let modulus = z.abs();
+    let mut value = Complex::default();
+    let mut slope = Complex::default();
+    let mut magnitude = T::ZERO;
+    for &ck in c.iter().rev() {
+        slope = slope*z + value;
+        value = value*z + ck;
+        magnitude = magnitude*modulus + ck.abs();
+    }
+    (value, slope, T::from_f64(4.0*c.len() as f64)*T::EPSILON*magnitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_cubic(x: f64) -> f64 {
+        (x*x*x) + (2.0*x*x) - 0.4
+    }
+
+    fn sample_polynomial() -> Polynomial {
+        Polynomial::new(vec![-0.4, 0.0, 2.0, 1.0])
+    }
+
+    #[test]
+    fn test_eval() {
+        let p = sample_polynomial();
+        assert_eq!(p.degree(), 3);
+        for x in [-2.0, -0.3, 0.0, 0.41, 1.7] {
+            assert!((p.eval(x) - sample_cubic(x)).abs() < 1e-14);
+            let (value, bound) = p.eval_with_error(x);
+            assert_eq!(value, p.eval(x));
+            assert!(bound < 1e-14);
+        }
+        let z = p.eval_complex(Complex::new(0.0, 1.0));
+        assert_eq!(z, Complex::new(-2.4, -1.0));
+        assert_eq!(Polynomial::<f64>::new(vec![0.0, 0.0]).eval(3.0), 0.0);
+        assert!(Polynomial::<f64>::new(vec![0.0, 0.0]).is_zero());
+    }
+
+    #[test]
+    fn test_equality_ignores_trailing_zeros() {
+        let p = Polynomial { coefficients: vec![1.0, 2.0, 0.0] };
+        assert_eq!(p, Polynomial { coefficients: vec![1.0, 2.0] });
+        assert_eq!(Polynomial { coefficients: vec![0.0] }, Polynomial::default());
+        assert_ne!(p, Polynomial::new(vec![1.0, 2.0, 3.0]));
+    }
+
+    #[test]
+    fn test_eval_compensated() {
+        // (x - 1)^6 expanded is badly conditioned near x = 1.
+        let p = Polynomial::from_roots(&[1.0; 6]);
+        let x = 1.001;
+        let exact = (x - 1.0f64).powi(6);
+        let (value, bound) = p.eval_with_error(x);
+        assert!((value - exact).abs() > 100.0*exact.abs());
+        assert!((value - exact).abs() <= bound);
+        assert!((p.eval_compensated(x) - exact).abs() < 1e-12*exact.abs());
+        assert_eq!(sample_polynomial().eval_compensated(0.5), sample_cubic(0.5));
+    }
+
+    #[test]
+    fn test_calculus() {
+        let p = sample_polynomial();
+        assert_eq!(p.derivative(), Polynomial::new(vec![0.0, 4.0, 3.0]));
+        assert_eq!(p.integral(), Polynomial::new(vec![0.0, -0.4, 0.0, 2.0/3.0, 0.25]));
+        assert_eq!(p.integral().derivative(), p);
+        assert!(Polynomial::new(vec![5.0]).derivative().is_zero());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Polynomial::new(vec![-1.0, 1.0]);
+        let b = Polynomial::new(vec![1.0, 1.0]);
+        assert_eq!(&a*&b, Polynomial::new(vec![-1.0, 0.0, 1.0]));
+        assert_eq!(&a + &b, Polynomial::new(vec![0.0, 2.0]));
+        assert_eq!(a.clone() - b.clone(), Polynomial::new(vec![-2.0]));
+        assert!((a.clone() - a.clone()).is_zero());
+        assert_eq!(-a.clone()*2.0, Polynomial::new(vec![2.0, -2.0]));
+        assert_eq!(Polynomial::from_roots(&[1.0, -1.0]), a*b);
+    }
+
+    #[test]
+    fn test_division() {
+        let p = sample_polynomial();
+        let d = Polynomial::new(vec![1.0, 0.0, 1.0]);
+        let (q, r) = p.div_rem(&d).unwrap();
+        assert_eq!(q, Polynomial::new(vec![2.0, 1.0]));
+        assert_eq!(r, Polynomial::new(vec![-2.4, -1.0]));
+        let product = &(&q*&d) + &r;
+        assert!(product.coefficients.iter().zip(&p.coefficients).all(|(a, b)| (a - b).abs() < 1e-15));
+        assert!(matches!(p.div_rem(&Polynomial::default()), Err(NumlError::InvalidInput { name: "divisor", .. })));
+
+        let (q, remainder) = Polynomial::from_roots(&[1.0, 2.0, 3.0]).deflate(1.0);
+        assert_eq!(q, Polynomial::from_roots(&[2.0, 3.0]));
+        assert_eq!(remainder, 0.0);
+        let (_, remainder) = p.deflate(0.5);
+        assert_eq!(remainder, sample_cubic(0.5));
+    }
+
+    #[test]
+    fn test_polynomial_roots() {
+        let roots = polynomial_roots(&sample_polynomial(), 100).unwrap();
+        assert_eq!(roots.len(), 3);
+        assert!(roots.iter().all(|r| r.converged && r.root.im.abs() <= r.error && r.error < 1e-13));
+        assert!(roots[2].root.re > 0.4 && roots[2].root.re < 0.41);
+        for root in &roots {
+            assert!(sample_cubic(root.root.re).abs() < 1e-15);
+        }
+
+        let roots = polynomial_roots(&Polynomial::new(vec![1.0, 0.0, 1.0]), 100).unwrap();
+        assert!((roots[0].root - Complex::new(0.0, -1.0)).abs() <= roots[0].error);
+        assert!((roots[1].root - Complex::new(0.0, 1.0)).abs() <= roots[1].error);
+        assert!(roots.iter().all(|r| r.error < 1e-14));
+
+        let roots = polynomial_roots(&Polynomial::new(vec![0.0, 0.0, -1.0, 1.0]), 100).unwrap();
+        assert_eq!(roots.iter().map(|r| r.root.re).collect::<Vec<_>>(), vec![0.0, 0.0, 1.0]);
+
+        assert!(matches!(polynomial_roots(&Polynomial::<f64>::default(), 100), Err(NumlError::InvalidInput { .. })));
+    }
+
+    #[test]
+    fn test_polynomial_roots_error_estimates() {
+        // Wilkinson's polynomial of degree 12, whose roots are sensitive to its coefficients.
+        let exact: Vec<f64> = (1..=12).map(|k| k as f64).collect();
+        let roots = polynomial_roots(&Polynomial::from_roots(&exact), 100).unwrap();
+        for (root, k) in roots.iter().zip(&exact) {
+            assert!(root.converged);
+            assert!((root.root - Complex::from(*k)).abs() <= root.error);
+            assert!(root.error < 1e-3);
+        }
+
+        // A double root at 1 is only found to about sqrt(EPS).
+        let roots = polynomial_roots(&Polynomial::from_roots(&[1.0, 1.0, -2.0]), 100).unwrap();
+        for root in &roots[1..] {
+            assert!((root.root - Complex::from(1.0)).abs() < 1e-6);
+            assert!((root.root - Complex::from(1.0)).abs() <= root.error);
+        }
+    }
+
+    #[test]
+    fn test_f32() {
+        let p = Polynomial::<f32>::from_roots(&[0.5, -2.0]);
+        let roots = polynomial_roots(&p, 100).unwrap();
+        assert!((roots[0].root.re + 2.0).abs() < 1e-6 && (roots[1].root.re - 0.5).abs() < 1e-6);
+        assert!((p.eval_compensated(0.25) - p.eval(0.25)).abs() < 1e-6);
+    }
+}
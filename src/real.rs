@@ -0,0 +1,149 @@
+//! The Real trait, which lets numl's algorithms work with any floating point type.
+
+use std::fmt;
+
+use crate::Scalar;
+
+/// A floating point type that numl's algorithms can be run with.
+///
+/// Every algorithm in numl that works with real numbers is generic over Real, and every step
+/// size, tolerance and round-off safeguard is derived from Real::EPSILON, so that the results are
+/// as accurate as the chosen type allows: f32 gets step sizes based on f32::EPSILON and f64 gets
+/// step sizes based on f64::EPSILON. Implementing Real (and Scalar) for an extended precision type
+/// makes all of numl available for it.
+///
+/// Arithmetic, the elementary functions and conversion from and to f64 come from Scalar; Real
+/// adds the constants and the floating point specific operations.
+pub trait Real: Scalar + Default + fmt::Debug + fmt::Display + 'static {
+    const ZERO: Self;
+    const ONE: Self;
+    const PI: Self;
+
+    /// The difference between 1 and the next larger representable number.
+    const EPSILON: Self;
+
+    /// The smallest positive normal number.
+    const MIN_POSITIVE: Self;
+
+    /// The largest finite number.
+    const MAX: Self;
+
+    const INFINITY: Self;
+    const NEG_INFINITY: Self;
+    const NAN: Self;
+
+    fn is_finite(self) -> bool;
+    fn is_nan(self) -> bool;
+    fn is_sign_negative(self) -> bool;
+
+    /// The larger of the two numbers, ignoring NaN.
+    fn max(self, other: Self) -> Self;
+
+    /// The smaller of the two numbers, ignoring NaN.
+    fn min(self, other: Self) -> Self;
+
+    fn signum(self) -> Self;
+    fn copysign(self, sign: Self) -> Self;
+
+    /// sqrt(self^2 + other^2), computed without undue overflow or underflow.
+    fn hypot(self, other: Self) -> Self;
+
+    /// The four quadrant arctangent of self/other.
+    fn atan2(self, other: Self) -> Self;
+}
+
+impl Real for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+    const PI: Self = std::f64::consts::PI;
+    const EPSILON: Self = f64::EPSILON;
+    const MIN_POSITIVE: Self = f64::MIN_POSITIVE;
+    const MAX: Self = f64::MAX;
+    const INFINITY: Self = f64::INFINITY;
+    const NEG_INFINITY: Self = f64::NEG_INFINITY;
+    const NAN: Self = f64::NAN;
+
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+
+    fn is_sign_negative(self) -> bool {
+        f64::is_sign_negative(self)
+    }
+
+    fn max(self, other: Self) -> Self {
+        f64::max(self, other)
+    }
+
+    fn min(self, other: Self) -> Self {
+        f64::min(self, other)
+    }
+
+    fn signum(self) -> Self {
+        f64::signum(self)
+    }
+
+    fn copysign(self, sign: Self) -> Self {
+        f64::copysign(self, sign)
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        f64::hypot(self, other)
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        f64::atan2(self, other)
+    }
+}
+
+impl Real for f32 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+    const PI: Self = std::f32::consts::PI;
+    const EPSILON: Self = f32::EPSILON;
+    const MIN_POSITIVE: Self = f32::MIN_POSITIVE;
+    const MAX: Self = f32::MAX;
+    const INFINITY: Self = f32::INFINITY;
+    const NEG_INFINITY: Self = f32::NEG_INFINITY;
+    const NAN: Self = f32::NAN;
+
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+
+    fn is_nan(self) -> bool {
+        f32::is_nan(self)
+    }
+
+    fn is_sign_negative(self) -> bool {
+        f32::is_sign_negative(self)
+    }
+
+    fn max(self, other: Self) -> Self {
+        f32::max(self, other)
+    }
+
+    fn min(self, other: Self) -> Self {
+        f32::min(self, other)
+    }
+
+    fn signum(self) -> Self {
+        f32::signum(self)
+    }
+
+    fn copysign(self, sign: Self) -> Self {
+        f32::copysign(self, sign)
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        f32::hypot(self, other)
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        f32::atan2(self, other)
+    }
+}
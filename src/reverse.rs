@@ -0,0 +1,605 @@
+//! Tape-based reverse-mode automatic differentiation.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+use crate::{Real, Scalar};
+
+/// One recorded operation: the indices of its (at most two) operands on the tape, together with
+/// the partial derivatives of the result with respect to each of them.
+#[derive(Debug, Clone, Copy)]
+struct Node<T> {
+    parents: [Option<(usize, T)>; 2],
+}
+
+/// A record of every operation performed on the Vars created from it.
+///
+/// Variables are created with Tape::var(), and every arithmetic operation or elementary function
+/// applied to them appends one entry to the tape. Calling Var::gradient() on the final result
+/// then walks the tape backwards once and produces the derivatives with respect to every
+/// variable, so a gradient costs a small constant multiple of one function evaluation no matter
+/// how many inputs there are.
+///
+/// The values and derivatives can be of any type implementing Real, and default to f64. Vars
+/// from different tapes must not be mixed; doing so panics.
+#[derive(Debug)]
+pub struct Tape<T = f64> {
+    /// Unique identifier, so that a Gradient can recognise its tape even after it was dropped.
+    id: usize,
+    nodes: RefCell<Vec<Node<T>>>,
+}
+
+/// Returns an identifier that no other tape has had.
+fn next_tape_id() -> usize {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    NEXT_ID.fetch_add(1, AtomicOrdering::Relaxed)
+}
+
+/// A real number recorded on a Tape.
+///
+/// Vars are cheap to copy and implement Scalar, so functions written generically over Scalar can
+/// be differentiated in reverse mode. Constants (created with Scalar::from_f64() or From<T>) are
+/// not recorded on any tape.
+#[derive(Clone, Copy)]
+pub struct Var<'t, T = f64> {
+    tape: Option<&'t Tape<T>>,
+    index: usize,
+    value: T,
+}
+
+/// The derivatives of a Var with respect to the variables on its tape, as computed by
+/// Var::gradient().
+///
+/// It remembers which tape it was computed on, and asking it for the derivative with respect to a
+/// Var from a different tape panics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient<T = f64> {
+    /// Identifier of the tape, or None for the gradient of a constant.
+    tape: Option<usize>,
+    adjoints: Vec<T>,
+}
+
+impl<T> Default for Tape<T> {
+    fn default() -> Self {
+        Tape { id: next_tape_id(), nodes: RefCell::default() }
+    }
+}
+
+impl<T: Real> Tape<T> {
+    /// Creates an empty tape.
+    pub fn new() -> Self {
+        Tape::default()
+    }
+
+    /// Creates a new independent variable with the given value.
+    pub fn var(&self, value: T) -> Var<'_, T> {
+        let index = self.push(Node { parents: [None, None] });
+        Var { tape: Some(self), index, value }
+    }
+
+    /// Creates one independent variable for every given value.
+    pub fn vars(&self, values: &[T]) -> Vec<Var<'_, T>> {
+        values.iter().map(|value| self.var(*value)).collect()
+    }
+
+    /// Number of entries recorded on the tape.
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    /// Returns true if nothing has been recorded on the tape.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Erases every entry of the tape, keeping its allocation for reuse. Gradients computed
+    /// before are no longer valid for the Vars recorded afterwards.
+    pub fn clear(&mut self) {
+        self.id = next_tape_id();
+        self.nodes.get_mut().clear();
+    }
+
+    fn push(&self, node: Node<T>) -> usize {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(node);
+        nodes.len() - 1
+    }
+}
+
+impl<'t, T: Real> Var<'t, T> {
+    /// Creates a constant, which is not recorded on any tape.
+    pub const fn constant(value: T) -> Self {
+        Var { tape: None, index: 0, value }
+    }
+
+    /// The value of this number.
+    pub fn value(self) -> T {
+        self.value
+    }
+
+    /// Performs the backward pass, computing the derivatives of this number with respect to every
+    /// variable recorded on its tape before it.
+    pub fn gradient(&self) -> Gradient<T> {
+        let tape = match self.tape {
+            Some(tape) => tape,
+            None => return Gradient { tape: None, adjoints: Vec::new() },
+        };
+        let nodes = tape.nodes.borrow();
+
+        let mut adjoints = vec![T::ZERO; self.index + 1];
+        adjoints[self.index] = T::ONE;
+        for i in (0..=self.index).rev() {
+            let adjoint = adjoints[i];
+            if adjoint == T::ZERO {
+                continue;
+            }
+            for &(parent, partial) in nodes[i].parents.iter().flatten() {
+                adjoints[parent] += partial*adjoint;
+            }
+        }
+
+        Gradient { tape: Some(tape.id), adjoints }
+    }
+
+    /// Records the result of a function of one variable with the given value and derivative.
+    fn unary(self, value: T, partial: T) -> Self {
+        match self.tape {
+            Some(tape) => {
+                let index = tape.push(Node { parents: [Some((self.index, partial)), None] });
+                Var { tape: Some(tape), index, value }
+            }
+            None => Var::constant(value),
+        }
+    }
+
+    /// Records the result of a function of two variables with the given value and partial
+    /// derivatives.
+    fn binary(self, other: Self, value: T, partial_self: T, partial_other: T) -> Self {
+        let tape = match (self.tape, other.tape) {
+            (Some(a), Some(b)) => {
+                assert!(ptr::eq(a, b), "Vars from different tapes cannot be combined");
+                a
+            }
+            (Some(tape), None) | (None, Some(tape)) => tape,
+            (None, None) => return Var::constant(value),
+        };
+        let parents = [
+            self.tape.map(|_| (self.index, partial_self)),
+            other.tape.map(|_| (other.index, partial_other)),
+        ];
+        let index = tape.push(Node { parents });
+        Var { tape: Some(tape), index, value }
+    }
+}
+
+impl<T: Real> Gradient<T> {
+    /// The derivative with respect to the given variable. This is zero for constants and for
+    /// variables the differentiated number does not depend on.
+    ///
+    /// Panics if var was recorded on a different tape than the differentiated number.
+    pub fn wrt(&self, var: Var<T>) -> T {
+        match (self.tape, var.tape) {
+            (Some(tape), Some(var_tape)) => {
+                assert_eq!(tape, var_tape.id, "Gradient and Var come from different tapes");
+                self.adjoints.get(var.index).copied().unwrap_or(T::ZERO)
+            }
+            _ => T::ZERO,
+        }
+    }
+
+    /// The derivatives with respect to each of the given variables.
+    pub fn wrt_all(&self, vars: &[Var<T>]) -> Vec<T> {
+        vars.iter().map(|var| self.wrt(*var)).collect()
+    }
+}
+
+/// Computes the value and gradient of a function of several variables using reverse-mode
+/// automatic differentiation.
+///
+/// Inputs:
+/// - f: impl FnOnce(&[Var<T>]) -> Var<T>
+/// - x: &[T]
+///
+/// f() is evaluated once on a fresh Tape, followed by a single backward pass. The easiest way to
+/// get such a function is to write it generically over Scalar, so that the same code can also be
+/// evaluated with f64 or f32. Returns f(x) together with the gradient of f() at x.
+pub fn gradient_ad<T: Real>(f: impl for<'t> FnOnce(&[Var<'t, T>]) -> Var<'t, T>, x: &[T]) -> (T, Vec<T>) {
+    let tape = Tape::new();
+    let vars = tape.vars(x);
+    let result = f(&vars);
+    (result.value, result.gradient().wrt_all(&vars))
+}
+
+impl<T: Real> fmt::Debug for Var<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.tape {
+            Some(_) => write!(f, "Var({} @ {})", self.value, self.index),
+            None => write!(f, "Var({})", self.value),
+        }
+    }
+}
+
+impl<T: Real> fmt::Display for Var<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl<T: Real> From<T> for Var<'_, T> {
+    fn from(value: T) -> Self {
+        Var::constant(value)
+    }
+}
+
+impl<T: Real> PartialEq for Var<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Real> PartialOrd for Var<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<T: Real> Neg for Var<'_, T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.unary(-self.value, -T::ONE)
+    }
+}
+
+impl<T: Real> Add for Var<'_, T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.binary(rhs, self.value + rhs.value, T::ONE, T::ONE)
+    }
+}
+
+impl<T: Real> Sub for Var<'_, T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.binary(rhs, self.value - rhs.value, T::ONE, -T::ONE)
+    }
+}
+
+impl<T: Real> Mul for Var<'_, T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.binary(rhs, self.value*rhs.value, rhs.value, self.value)
+    }
+}
+
+impl<T: Real> Div for Var<'_, T> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let quotient = self.value/rhs.value;
+        self.binary(rhs, quotient, T::ONE/rhs.value, -quotient/rhs.value)
+    }
+}
+
+impl<T: Real> Add<T> for Var<'_, T> {
+    type Output = Self;
+
+    fn add(self, rhs: T) -> Self {
+        self.unary(self.value + rhs, T::ONE)
+    }
+}
+
+impl<T: Real> Sub<T> for Var<'_, T> {
+    type Output = Self;
+
+    fn sub(self, rhs: T) -> Self {
+        self.unary(self.value - rhs, T::ONE)
+    }
+}
+
+impl<T: Real> Mul<T> for Var<'_, T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        self.unary(self.value*rhs, rhs)
+    }
+}
+
+impl<T: Real> Div<T> for Var<'_, T> {
+    type Output = Self;
+
+    fn div(self, rhs: T) -> Self {
+        self.unary(self.value/rhs, T::ONE/rhs)
+    }
+}
+
+/// Implements arithmetic with a real number on the left and a Var on the right, which the orphan
+/// rules only allow for concrete types.
+macro_rules! impl_real_lhs_ops {
+    ($($t:ty),*) => {$(
+        impl<'t> Add<Var<'t, $t>> for $t {
+            type Output = Var<'t, $t>;
+
+            fn add(self, rhs: Var<'t, $t>) -> Var<'t, $t> {
+                rhs + self
+            }
+        }
+
+        impl<'t> Sub<Var<'t, $t>> for $t {
+            type Output = Var<'t, $t>;
+
+            fn sub(self, rhs: Var<'t, $t>) -> Var<'t, $t> {
+                rhs.unary(self - rhs.value, -1.0)
+            }
+        }
+
+        impl<'t> Mul<Var<'t, $t>> for $t {
+            type Output = Var<'t, $t>;
+
+            fn mul(self, rhs: Var<'t, $t>) -> Var<'t, $t> {
+                rhs*self
+            }
+        }
+
+        impl<'t> Div<Var<'t, $t>> for $t {
+            type Output = Var<'t, $t>;
+
+            fn div(self, rhs: Var<'t, $t>) -> Var<'t, $t> {
+                let quotient = self/rhs.value;
+                rhs.unary(quotient, -quotient/rhs.value)
+            }
+        }
+    )*};
+}
+
+impl_real_lhs_ops!(f32, f64);
+
+impl<T: Real> AddAssign for Var<'_, T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Real> SubAssign for Var<'_, T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T: Real> MulAssign for Var<'_, T> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self*rhs;
+    }
+}
+
+impl<T: Real> DivAssign for Var<'_, T> {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self/rhs;
+    }
+}
+
+impl<T: Real> Scalar for Var<'_, T> {
+    fn from_f64(value: f64) -> Self {
+        Var::constant(T::from_f64(value))
+    }
+
+    fn value(self) -> f64 {
+        self.value.value()
+    }
+
+    fn abs(self) -> Self {
+        if self.value < T::ZERO { -self } else { self }
+    }
+
+    fn sqrt(self) -> Self {
+        let root = self.value.sqrt();
+        self.unary(root, T::from_f64(0.5)/root)
+    }
+
+    fn cbrt(self) -> Self {
+        let root = self.value.cbrt();
+        self.unary(root, T::ONE/(T::from_f64(3.0)*root*root))
+    }
+
+    fn exp(self) -> Self {
+        let exp = self.value.exp();
+        self.unary(exp, exp)
+    }
+
+    fn ln(self) -> Self {
+        self.unary(self.value.ln(), T::ONE/self.value)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        if n == 0 {
+            return Var::constant(T::ONE);
+        }
+        self.unary(self.value.powi(n), T::from_f64(n as f64)*self.value.powi(n - 1))
+    }
+
+    fn powf(self, p: f64) -> Self {
+        if p == 0.0 {
+            return Var::constant(T::ONE);
+        }
+        self.unary(self.value.powf(p), T::from_f64(p)*self.value.powf(p - 1.0))
+    }
+
+    fn sin(self) -> Self {
+        self.unary(self.value.sin(), self.value.cos())
+    }
+
+    fn cos(self) -> Self {
+        self.unary(self.value.cos(), -self.value.sin())
+    }
+
+    fn tan(self) -> Self {
+        let tan = self.value.tan();
+        self.unary(tan, T::ONE + tan*tan)
+    }
+
+    fn asin(self) -> Self {
+        self.unary(self.value.asin(), T::ONE/(T::ONE - self.value*self.value).sqrt())
+    }
+
+    fn acos(self) -> Self {
+        self.unary(self.value.acos(), -T::ONE/(T::ONE - self.value*self.value).sqrt())
+    }
+
+    fn atan(self) -> Self {
+        self.unary(self.value.atan(), T::ONE/(T::ONE + self.value*self.value))
+    }
+
+    fn sinh(self) -> Self {
+        self.unary(self.value.sinh(), self.value.cosh())
+    }
+
+    fn cosh(self) -> Self {
+        self.unary(self.value.cosh(), self.value.sinh())
+    }
+
+    fn tanh(self) -> Self {
+        let tanh = self.value.tanh();
+        self.unary(tanh, T::ONE - tanh*tanh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rosenbrock<T: Scalar>(x: &[T]) -> T {
+        let one = T::from_f64(1.0);
+        let hundred = T::from_f64(100.0);
+        let mut sum = T::from_f64(0.0);
+        for i in 0..x.len() - 1 {
+            sum += hundred*(x[i+1] - x[i]*x[i]).powi(2) + (one - x[i]).powi(2);
+        }
+        sum
+    }
+
+    #[test]
+    fn test_gradient() {
+        let tape = Tape::new();
+        let x = tape.var(2.0);
+        let y = tape.var(3.0);
+        let z = x*y + x.sin() - y/x;
+        let gradient = z.gradient();
+        assert_eq!(z.value(), 6.0 + f64::sin(2.0) - 1.5);
+        assert_eq!(gradient.wrt(x), 3.0 + f64::cos(2.0) + 3.0/4.0);
+        assert_eq!(gradient.wrt(y), 2.0 - 0.5);
+        assert_eq!(gradient.wrt(Var::constant(1.0)), 0.0);
+    }
+
+    #[test]
+    fn test_gradient_ad_rosenbrock() {
+        let (value, gradient) = gradient_ad(|x| rosenbrock(x), &[1.2, 1.0]);
+        assert_eq!(value, rosenbrock(&[1.2, 1.0]));
+        assert!((gradient[0] - (-400.0*1.2*(1.0 - 1.44) - 2.0*(1.0 - 1.2))).abs() < 1e-12);
+        assert!((gradient[1] - 200.0*(1.0 - 1.44)).abs() < 1e-12);
+
+        let (value, gradient) = gradient_ad(|x| rosenbrock(x), &[1.0; 4]);
+        assert_eq!(value, 0.0);
+        assert_eq!(gradient, vec![0.0; 4]);
+    }
+
+    #[test]
+    fn test_gradient_cost_is_independent_of_inputs() {
+        // One backward pass gives all 300 partial derivatives, and the tape only grows by a
+        // constant number of entries per input.
+        let n = 300;
+        let x: Vec<f64> = (0..n).map(|i| i as f64/n as f64).collect();
+        let tape = Tape::new();
+        let vars = tape.vars(&x);
+        let result = rosenbrock(&vars);
+        assert!(tape.len() < 10*n);
+
+        let gradient = result.gradient().wrt_all(&vars);
+        for i in 1..n - 1 {
+            let expected = -400.0*x[i]*(x[i+1] - x[i]*x[i]) - 2.0*(1.0 - x[i]) + 200.0*(x[i] - x[i-1]*x[i-1]);
+            assert!((gradient[i] - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_mixed_constants() {
+        let tape: Tape = Tape::new();
+        let x = tape.var(0.5);
+        let y = 2.0/x + 3.0*x - 1.0 + Var::from(4.0)*x.exp();
+        let expected = -2.0/0.25 + 3.0 + 4.0*f64::exp(0.5);
+        assert!((y.gradient().wrt(x) - expected).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_f32() {
+        let tape = Tape::new();
+        let x = tape.var(2.0f32);
+        let y = x*x*x - 3.0f32*x + Var::from(0.5f32)*x.sqrt();
+        assert_eq!(y.value(), 2.0 + 0.5*2.0f32.sqrt());
+        assert!((y.gradient().wrt(x) - (9.0 + 0.25/2.0f32.sqrt())).abs() <= 8.0*f32::EPSILON);
+
+        let (value, gradient) = gradient_ad(|x| rosenbrock(x), &[1.2f32, 1.0]);
+        assert_eq!(value, rosenbrock(&[1.2f32, 1.0]));
+        assert!((gradient[1] - 200.0*(1.0 - 1.44)).abs() <= 1e-4);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut tape = Tape::new();
+        let _ = tape.var(1.0)*tape.var(2.0);
+        assert_eq!(tape.len(), 3);
+        tape.clear();
+        assert!(tape.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "different tapes")]
+    fn test_mixing_tapes_panics() {
+        let a = Tape::new();
+        let b = Tape::new();
+        let _ = a.var(1.0) + b.var(2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "different tapes")]
+    fn test_gradient_wrt_other_tape_panics() {
+        let a = Tape::new();
+        let b = Tape::new();
+        let x = a.var(1.0);
+        let y = b.var(2.0);
+        let gradient = (x*x).gradient();
+        assert_eq!(gradient.wrt(x), 2.0);
+        let _ = gradient.wrt(y);
+    }
+
+    #[test]
+    #[should_panic(expected = "different tapes")]
+    fn test_gradient_wrt_later_tape_panics() {
+        // A tape created after another one was dropped can occupy the same memory, but is still
+        // recognised as a different tape.
+        let mut gradient: Option<Gradient> = None;
+        for value in [2.0, 3.0] {
+            let tape = Tape::new();
+            let x = tape.var(value);
+            if let Some(gradient) = &gradient {
+                let _ = gradient.wrt(x);
+            }
+            gradient = Some(x.powi(3).gradient());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "different tapes")]
+    fn test_gradient_wrt_cleared_tape_panics() {
+        let mut tape = Tape::new();
+        let gradient = tape.var(2.0).powi(3).gradient();
+        tape.clear();
+        let _ = gradient.wrt(tape.var(2.0));
+    }
+}
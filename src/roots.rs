@@ -0,0 +1,1047 @@
+//! Iterative root-finding drivers.
+
+use crate::diff::check_typ;
+use crate::{derivative_mut, NumlError, Real};
+
+/// The reason an iterative root finder stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// f() evaluated to exactly zero at the returned root.
+    ExactRoot,
+
+    /// The last step in x was within the requested absolute/relative tolerance.
+    StepTolerance,
+
+    /// |f(x)| fell below the requested residual tolerance.
+    ResidualTolerance,
+
+    /// The bracket around the root became no wider than the requested tolerance, or its endpoints
+    /// became adjacent floating point numbers.
+    BracketTolerance,
+
+    /// A small step failed to decrease |f|, as happens once rounding errors in f() dominate near
+    /// a multiple root.
+    NoiseLimit,
+}
+
+/// Summary of a successful root-finding run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RootReport<T = f64> {
+    /// The computed root.
+    pub root: T,
+
+    /// Number of iterations performed.
+    pub iterations: usize,
+
+    /// Total number of evaluations of f(), including those spent on numerical derivatives.
+    pub evaluations: usize,
+
+    /// |f(root)|.
+    pub residual: T,
+
+    /// Which stopping criterion was met.
+    pub termination: Termination,
+}
+
+/// Options controlling newton_solve().
+///
+/// The iteration stops as soon as one of the following holds:
+/// - f(x) is exactly zero
+/// - |f(x)| <= f_tol
+/// - |x_new - x| <= x_abs_tol + x_rel_tol*|x_new|
+///
+/// If none of these holds after max_iterations iterations, a NumlError::NoConvergence is
+/// returned. typ is passed on to derivative(); see NumlError::TypError for details.
+///
+/// max_step bounds the size of a single step relative to the scale of x: a step with
+/// |f(x)/f'(x)| > max_step*max(|x|, |typ|) is rejected with a NumlError::IllConditioned, since it
+/// comes from a derivative too close to zero to be trusted.
+///
+/// The default tolerances are derived from the machine epsilon of T, and the default max_step is
+/// 1/sqrt(EPS).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NewtonOptions<T = f64> {
+    pub x_abs_tol: T,
+    pub x_rel_tol: T,
+    pub f_tol: T,
+    pub max_iterations: usize,
+    pub typ: T,
+    pub max_step: T,
+}
+
+impl<T: Real> Default for NewtonOptions<T> {
+    fn default() -> Self {
+        NewtonOptions {
+            x_abs_tol: T::EPSILON,
+            x_rel_tol: T::from_f64(4.0)*T::EPSILON,
+            f_tol: T::ZERO,
+            max_iterations: 100,
+            typ: T::ONE,
+            max_step: T::ONE/T::EPSILON.sqrt(),
+        }
+    }
+}
+
+/// Wraps a function and counts how many times it has been evaluated.
+pub(crate) struct Counted<F> {
+    f: F,
+    pub(crate) evaluations: usize,
+}
+
+impl<F> Counted<F> {
+    pub(crate) fn new(f: F) -> Self {
+        Counted { f, evaluations: 0 }
+    }
+
+    pub(crate) fn eval<T, U>(&mut self, x: T) -> U where F: FnMut(T) -> U {
+        self.evaluations += 1;
+        (self.f)(x)
+    }
+}
+
+/// Computes the Newton step x - fx/derivative, rejecting it if any of the inputs is unusable or if
+/// the step is larger than max_step*max(|x|, |typ|).
+pub(crate) fn newton_step<T: Real>(x: T, fx: T, derivative: T, typ: T, max_step: T) -> Result<T, NumlError> {
+    if !(fx.is_finite() && derivative.is_finite()) {
+        return Err(NumlError::NonFiniteValue { x: x.value(), fx: fx.value(), derivative: derivative.value() });
+    }
+    if derivative == T::ZERO {
+        return Err(NumlError::DerivativeZeroError);
+    }
+    let step = fx/derivative;
+    // Also rejects steps which overflow to infinity.
+    if step.abs() > max_step*x.abs().max(typ.abs()) {
+        return Err(NumlError::IllConditioned { x: x.value(), fx: fx.value(), derivative: derivative.value() });
+    }
+    Ok(x - step)
+}
+
+/// Finds a root of f() by iterating the quasi-Newton step of nqn() until convergence.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x0: T
+/// - options: NewtonOptions<T>
+///
+/// f() is the function whose root is being computed and x0 is the initial guess. Any function or
+/// closure can be passed, including closures that mutate their captured state.
+///
+/// Each iteration costs three evaluations of f(): two for the numerical derivative and one at the
+/// new iterate (the value at the old iterate is reused), plus one evaluation at x0.
+///
+/// Errors:
+/// - NumlError::TypError if options.typ is zero
+/// - NumlError::InvalidInput if options.typ is NaN or infinite
+/// - NumlError::NonFiniteValue if f() or its derivative evaluates to NaN or an infinity
+/// - NumlError::DerivativeZeroError if the derivative evaluates to exactly zero at an iterate
+/// - NumlError::IllConditioned if a step exceeds the bound set by options.max_step
+/// - NumlError::Divergence if an iterate stops being finite
+/// - NumlError::NoConvergence if no stopping criterion is met within options.max_iterations
+pub fn newton_solve<T: Real>(f: impl FnMut(T) -> T, x0: T, options: NewtonOptions<T>) -> Result<RootReport<T>, NumlError> {
+    let mut f = Counted::new(f);
+
+    let mut x = x0;
+    let mut fx = f.eval(x);
+    if !fx.is_finite() {
+        return Err(NumlError::NonFiniteValue { x: x.value(), fx: fx.value(), derivative: f64::NAN });
+    }
+
+    let mut iterations = 0;
+    loop {
+        let termination = if fx == T::ZERO {
+            Some(Termination::ExactRoot)
+        } else if fx.abs() <= options.f_tol {
+            Some(Termination::ResidualTolerance)
+        } else {
+            None
+        };
+        if let Some(termination) = termination {
+            return Ok(RootReport { root: x, iterations, evaluations: f.evaluations, residual: fx.abs(), termination });
+        }
+
+        let computed_derivative = derivative_mut(|t| f.eval(t), x, options.typ)?;
+        if iterations >= options.max_iterations {
+            return Err(NumlError::NoConvergence { iterations, x: x.value(), fx: fx.value(), derivative: computed_derivative.value() });
+        }
+
+        let next = newton_step(x, fx, computed_derivative, options.typ, options.max_step)?;
+        if !next.is_finite() {
+            return Err(NumlError::Divergence { iterations, x: x.value() });
+        }
+        let f_next = f.eval(next);
+        if !f_next.is_finite() {
+            return Err(NumlError::NonFiniteValue { x: next.value(), fx: f_next.value(), derivative: f64::NAN });
+        }
+        iterations += 1;
+
+        if (next - x).abs() <= options.x_abs_tol + options.x_rel_tol*next.abs() {
+            let termination = if f_next == T::ZERO { Termination::ExactRoot } else { Termination::StepTolerance };
+            return Ok(RootReport { root: next, iterations, evaluations: f.evaluations, residual: f_next.abs(), termination });
+        }
+
+        x = next;
+        fx = f_next;
+    }
+}
+
+/// Finds a root of f() with a Householder method of order N-1, using derivatives supplied by the
+/// caller.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> [T; N]
+/// - x0: T
+/// - options: NewtonOptions<T>
+///
+/// f() returns the value of the function and its first N-1 derivatives, [f(x), f'(x), ...,
+/// f^(N-1)(x)], so that N = 2 gives Newton's method and N = 3 Halley's method (see newton() and
+/// halley()). The Householder method of order d = N-1 steps from x to
+/// x + d*(1/f)^(d-1)(x)/(1/f)^(d)(x), and converges with order d+1 near a simple root. Higher
+/// orders need fewer iterations, which pays off when the derivatives are cheap, for example when
+/// they are known analytically or come from automatic differentiation:
+///
+/// ```
+/// use numl::{householder, Dual, NewtonOptions, Scalar};
+///
+/// fn cubic<T: Scalar>(x: T) -> T {
+///     x*x*x + T::from_f64(2.0)*x*x - T::from_f64(0.4)
+/// }
+///
+/// let report = householder(|x| {
+///     let y = cubic(Dual::variable(x));
+///     [y.re, y.eps]
+/// }, 1.0, NewtonOptions::default()).unwrap();
+/// assert!(cubic(report.root).abs() < 1e-15);
+/// ```
+///
+/// The step is computed from polynomials in f and its derivatives rather than from powers of
+/// 1/f, so it stays accurate as f(x) approaches zero. The stopping criteria and options are those
+/// of newton_solve(), with typ only used to scale options.max_step. Each iteration calls f() once,
+/// and evaluations in the returned report counts these calls.
+///
+/// Errors:
+/// - NumlError::InvalidInput if N < 2, or if options.typ is NaN or infinite
+/// - NumlError::TypError if options.typ is zero
+/// - NumlError::NonFiniteValue if f() or one of its derivatives evaluates to NaN or an infinity
+/// - NumlError::DerivativeZeroError if the denominator of the step is exactly zero, which for
+///   Newton's method means that f'(x) is zero
+/// - NumlError::IllConditioned if a step exceeds the bound set by options.max_step
+/// - NumlError::Divergence if an iterate stops being finite
+/// - NumlError::NoConvergence if no stopping criterion is met within options.max_iterations
+///
+/// All errors that carry a derivative report f'(x).
+pub fn householder<T: Real, const N: usize>(f: impl FnMut(T) -> [T; N], x0: T, options: NewtonOptions<T>) -> Result<RootReport<T>, NumlError> {
+    if N < 2 {
+        return Err(NumlError::InvalidInput { name: "order", value: N as f64 - 1.0 });
+    }
+    check_typ(options.typ)?;
+    let mut f = Counted::new(f);
+
+    let mut x = x0;
+    let mut values: [T; N] = f.eval(x);
+    let mut iterations = 0;
+    loop {
+        if !values.iter().all(|v| v.is_finite()) {
+            return Err(NumlError::NonFiniteValue { x: x.value(), fx: values[0].value(), derivative: values[1].value() });
+        }
+        let fx = values[0];
+        let termination = if fx == T::ZERO {
+            Some(Termination::ExactRoot)
+        } else if fx.abs() <= options.f_tol {
+            Some(Termination::ResidualTolerance)
+        } else {
+            None
+        };
+        if let Some(termination) = termination {
+            return Ok(RootReport { root: x, iterations, evaluations: f.evaluations, residual: fx.abs(), termination });
+        }
+
+        if iterations >= options.max_iterations {
+            return Err(NumlError::NoConvergence { iterations, x: x.value(), fx: fx.value(), derivative: values[1].value() });
+        }
+
+        let step = householder_step(&values);
+        if step.is_nan() {
+            return Err(NumlError::DerivativeZeroError);
+        }
+        if step.abs() > options.max_step*x.abs().max(options.typ.abs()) {
+            return Err(NumlError::IllConditioned { x: x.value(), fx: fx.value(), derivative: values[1].value() });
+        }
+        let next = x + step;
+        if !next.is_finite() {
+            return Err(NumlError::Divergence { iterations, x: x.value() });
+        }
+        let next_values: [T; N] = f.eval(next);
+        iterations += 1;
+
+        if (next - x).abs() <= options.x_abs_tol + options.x_rel_tol*next.abs() && next_values[0].is_finite() {
+            let termination = if next_values[0] == T::ZERO { Termination::ExactRoot } else { Termination::StepTolerance };
+            return Ok(RootReport { root: next, iterations, evaluations: f.evaluations, residual: next_values[0].abs(), termination });
+        }
+
+        x = next;
+        values = next_values;
+    }
+}
+
+/// Computes the Householder step d*f*G[d-1]/G[d] of order d = N-1, where
+/// G[n] = f^(n+1)*(1/f)^(n). The G[n] follow from differentiating f*(1/f) = 1 n times:
+/// G[0] = 1 and G[n] = -sum_{k=1}^{n} C(n,k)*f^(k)*f^(k-1)*G[n-k]. Returns NaN if G[d] is zero.
+fn householder_step<T: Real, const N: usize>(values: &[T; N]) -> T {
+    let d = N - 1;
+    let f = values[0];
+    let mut g = vec![T::ONE; N];
+    for n in 1..=d {
+        let mut sum = T::ZERO;
+        let mut binomial = 1.0;
+        let mut f_power = T::ONE;
+        for k in 1..=n {
+            binomial = binomial*(n + 1 - k) as f64/k as f64;
+            sum += T::from_f64(binomial)*values[k]*f_power*g[n-k];
+            f_power *= f;
+        }
+        g[n] = -sum;
+    }
+    if g[d] == T::ZERO {
+        return T::NAN;
+    }
+    T::from_f64(d as f64)*f*g[d-1]/g[d]
+}
+
+/// Finds a root of f() with Newton's method, using a derivative supplied by the caller.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - df: impl FnMut(T) -> T
+/// - x0: T
+/// - options: NewtonOptions<T>
+///
+/// df() is the derivative of f(). This is newton_solve() with the finite difference derivative
+/// replaced by df(), so it needs one evaluation of f() and one of df() per iteration instead of
+/// three evaluations of f(), and converges quadratically right down to the root. See
+/// householder() for the details and errors; evaluations in the returned report counts
+/// evaluations of f().
+pub fn newton<T: Real>(mut f: impl FnMut(T) -> T, mut df: impl FnMut(T) -> T, x0: T, options: NewtonOptions<T>) -> Result<RootReport<T>, NumlError> {
+    householder(|x| [f(x), df(x)], x0, options)
+}
+
+/// Finds a root of f() with Halley's method, using derivatives supplied by the caller.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - df: impl FnMut(T) -> T
+/// - d2f: impl FnMut(T) -> T
+/// - x0: T
+/// - options: NewtonOptions<T>
+///
+/// df() and d2f() are the first and second derivatives of f(). Halley's method steps from x to
+/// x - 2*f*f'/(2*f'^2 - f*f'') and converges cubically near a simple root. See householder() for
+/// the details and errors; evaluations in the returned report counts evaluations of f().
+pub fn halley<T: Real>(mut f: impl FnMut(T) -> T, mut df: impl FnMut(T) -> T, mut d2f: impl FnMut(T) -> T, x0: T, options: NewtonOptions<T>) -> Result<RootReport<T>, NumlError> {
+    householder(|x| [f(x), df(x), d2f(x)], x0, options)
+}
+
+/// Options controlling damped_newton().
+///
+/// newton holds the stopping criteria and step bound, which have the same meaning as for
+/// newton_solve(). A step of fraction lambda of the full Newton step is accepted when
+/// |f(x + lambda*p)| <= (1 - armijo*lambda)*|f(x)|, and lambda is halved until this holds or
+/// lambda drops below min_step. armijo must lie in (0, 1) and min_step in (0, 1]. The defaults
+/// are armijo = 1e-4 and min_step = 1e-6.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DampedNewtonOptions<T = f64> {
+    pub newton: NewtonOptions<T>,
+    pub armijo: T,
+    pub min_step: T,
+}
+
+impl<T: Real> Default for DampedNewtonOptions<T> {
+    fn default() -> Self {
+        DampedNewtonOptions {
+            newton: NewtonOptions::default(),
+            armijo: T::from_f64(1e-4),
+            min_step: T::from_f64(1e-6),
+        }
+    }
+}
+
+/// Summary of a successful damped_newton() run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DampedNewtonReport<T = f64> {
+    /// The usual root-finding summary.
+    pub report: RootReport<T>,
+
+    /// Total number of times a step was halved over all iterations.
+    pub reductions: usize,
+}
+
+/// Finds a root of f() with a quasi-Newton iteration globalised by a backtracking line search.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x0: T
+/// - options: DampedNewtonOptions<T>
+///
+/// newton_solve() always takes the full step x - f(x)/f'(x), which far from the root can
+/// overshoot so badly that the iteration diverges, as it does for atan() started beyond about
+/// |x| = 1.39. This computes the same step with derivative_mut(), but only moves a fraction
+/// lambda of it: starting from lambda = 1, lambda is halved until |f| decreases enough to satisfy
+/// the Armijo condition (see DampedNewtonOptions). Since the Newton direction is always a descent
+/// direction for |f|, a small enough step is always acceptable for a smooth f(), and near the root
+/// the full step is accepted, which keeps quadratic convergence. Trial points where f() is NaN or
+/// infinite are treated like points where |f| does not decrease, so the line search also pulls
+/// steps back into the domain of f().
+///
+/// The iteration stops as described for newton_solve(), where the step tolerance is applied to
+/// the full Newton step. Each iteration costs two evaluations for the derivative plus one for
+/// every trial point. The returned report also counts how many times steps were halved.
+///
+/// Errors are those of newton_solve(), plus a NumlError::LineSearchFailed when lambda drops below
+/// options.min_step without any trial point being acceptable, which usually means that the
+/// iteration is stuck near a local minimum of |f| that is not a root, and a
+/// NumlError::InvalidInput if options.armijo or options.min_step is out of range.
+pub fn damped_newton<T: Real>(f: impl FnMut(T) -> T, x0: T, options: DampedNewtonOptions<T>) -> Result<DampedNewtonReport<T>, NumlError> {
+    let newton = options.newton;
+    if !(options.armijo > T::ZERO && options.armijo < T::ONE) {
+        return Err(NumlError::InvalidInput { name: "armijo", value: options.armijo.value() });
+    }
+    if !(options.min_step > T::ZERO && options.min_step <= T::ONE) {
+        return Err(NumlError::InvalidInput { name: "min_step", value: options.min_step.value() });
+    }
+    let mut f = Counted::new(f);
+
+    let mut x = x0;
+    let mut fx = f.eval(x);
+    if !fx.is_finite() {
+        return Err(NumlError::NonFiniteValue { x: x.value(), fx: fx.value(), derivative: f64::NAN });
+    }
+
+    let half = T::from_f64(0.5);
+    let mut iterations = 0;
+    let mut reductions = 0;
+    loop {
+        let termination = if fx == T::ZERO {
+            Some(Termination::ExactRoot)
+        } else if fx.abs() <= newton.f_tol {
+            Some(Termination::ResidualTolerance)
+        } else {
+            None
+        };
+        if let Some(termination) = termination {
+            let report = RootReport { root: x, iterations, evaluations: f.evaluations, residual: fx.abs(), termination };
+            return Ok(DampedNewtonReport { report, reductions });
+        }
+
+        let computed_derivative = derivative_mut(|t| f.eval(t), x, newton.typ)?;
+        if iterations >= newton.max_iterations {
+            return Err(NumlError::NoConvergence { iterations, x: x.value(), fx: fx.value(), derivative: computed_derivative.value() });
+        }
+        let full_step = newton_step(x, fx, computed_derivative, newton.typ, newton.max_step)? - x;
+        let converged = full_step.abs() <= newton.x_abs_tol + newton.x_rel_tol*(x + full_step).abs();
+
+        let mut lambda = T::ONE;
+        let (next, f_next) = loop {
+            let trial = x + lambda*full_step;
+            if !trial.is_finite() {
+                return Err(NumlError::Divergence { iterations, x: x.value() });
+            }
+            let f_trial = f.eval(trial);
+            // A converged step is taken in full, as |f| may not decrease at the level of
+            // round-off.
+            if f_trial.is_finite() && (converged || f_trial.abs() <= (T::ONE - options.armijo*lambda)*fx.abs()) {
+                break (trial, f_trial);
+            }
+            lambda *= half;
+            reductions += 1;
+            if lambda < options.min_step {
+                return Err(NumlError::LineSearchFailed { x: x.value(), fx: fx.value(), derivative: computed_derivative.value() });
+            }
+        };
+        iterations += 1;
+
+        if converged {
+            let termination = if f_next == T::ZERO { Termination::ExactRoot } else { Termination::StepTolerance };
+            let report = RootReport { root: next, iterations, evaluations: f.evaluations, residual: f_next.abs(), termination };
+            return Ok(DampedNewtonReport { report, reductions });
+        }
+
+        x = next;
+        fx = f_next;
+    }
+}
+
+/// Options controlling deflated_newton().
+///
+/// newton holds the stopping criteria and step bound used for every solve, with the same meaning
+/// as for newton_solve(). If polish is set, each root of the deflated function is refined by a
+/// newton_solve() on the original function before it is deflated, with f_tol set to zero so that it
+/// is polished to full precision. A root is only accepted if
+/// |f(root)| <= residual_tol for the original f(), in the units of f().
+///
+/// The defaults are polish = true and residual_tol = sqrt(EPS).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeflationOptions<T = f64> {
+    pub newton: NewtonOptions<T>,
+    pub polish: bool,
+    pub residual_tol: T,
+}
+
+impl<T: Real> Default for DeflationOptions<T> {
+    fn default() -> Self {
+        DeflationOptions {
+            newton: NewtonOptions::default(),
+            polish: true,
+            residual_tol: T::EPSILON.sqrt(),
+        }
+    }
+}
+
+/// A root found by deflated_newton().
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeflatedRoot<T = f64> {
+    /// The root, polished if polishing was enabled and improved it.
+    pub root: T,
+
+    /// |f(root)| for the original, undeflated f().
+    pub residual: T,
+
+    /// The summary of the solve on the deflated function.
+    pub deflated: RootReport<T>,
+
+    /// The summary of polishing against the original f(), if it was done and accepted.
+    pub polished: Option<RootReport<T>>,
+}
+
+/// Summary of a deflated_newton() run.
+#[derive(Debug)]
+pub struct DeflationReport<T = f64> {
+    /// The roots found, in the order they were found.
+    pub roots: Vec<DeflatedRoot<T>>,
+
+    /// Total number of evaluations of f().
+    pub evaluations: usize,
+
+    /// The error which ended the search before the requested number of roots was found, if any.
+    pub failure: Option<NumlError>,
+}
+
+/// Finds up to count roots of f() by Newton iteration on f() with the roots found so far divided
+/// out.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - x0: T
+/// - count: usize
+/// - options: DeflationOptions<T>
+///
+/// Restarting newton_solve() from another guess often converges to a root that is already known.
+/// Instead, every search here starts from x0 and iterates on the deflated function
+/// g(x) = f(x)/((x - r_1)*...*(x - r_k)), where r_1, ..., r_k are the roots found so far. Every
+/// simple root of f() other than the r_i is still a root of g(), but the r_i are not, so each
+/// search converges to a new root. A root of multiplicity m can be found up to m times.
+///
+/// A root of g() which is slightly off a root r of f() leaves a pole of g() right next to r, and
+/// g() keeps a root there which later searches may find again. To prevent this, each root of g()
+/// is by default polished with newton_solve() on f() itself, keeping the polished root only if it
+/// lowers |f| and does not land on a root that was already found, within the step tolerance of
+/// options.newton. As g() also tends to zero far away from the roots whenever f() grows more slowly
+/// than the product of the divided out factors, every root is checked against f(): if
+/// |f(root)| > options.residual_tol, the search stops with a NumlError::SpuriousRoot.
+///
+/// The search also stops when newton_solve() on g() fails, for example because f() has no more
+/// roots to converge to. Either way the roots found until then are returned, along with the
+/// error that ended the search in the report's failure field. x0 should not be a root of f(),
+/// since g() is not finite at the roots that have been divided out.
+///
+/// Errors:
+/// - NumlError::TypError if options.newton.typ is zero
+/// - NumlError::InvalidInput if options.newton.typ is NaN or infinite, or if
+///   options.residual_tol is negative or NaN
+pub fn deflated_newton<T: Real>(f: impl FnMut(T) -> T, x0: T, count: usize, options: DeflationOptions<T>) -> Result<DeflationReport<T>, NumlError> {
+    check_typ(options.newton.typ)?;
+    if options.residual_tol.is_nan() || options.residual_tol < T::ZERO {
+        return Err(NumlError::InvalidInput { name: "residual_tol", value: options.residual_tol.value() });
+    }
+    let mut f = Counted::new(f);
+
+    let mut roots: Vec<DeflatedRoot<T>> = Vec::with_capacity(count);
+    let mut failure = None;
+    while roots.len() < count {
+        let deflated = newton_solve(|x| {
+            let mut gx = f.eval(x);
+            for known in &roots {
+                gx /= x - known.root;
+            }
+            gx
+        }, x0, options.newton);
+        let deflated = match deflated {
+            Ok(report) => report,
+            Err(error) => {
+                failure = Some(error);
+                break;
+            }
+        };
+
+        let mut root = deflated.root;
+        let mut residual = f.eval(root).abs();
+        let mut polished = None;
+        if options.polish && residual != T::ZERO {
+            let polish_options = NewtonOptions { f_tol: T::ZERO, ..options.newton };
+            // Polishing may slide onto a root that was already found, which would then be divided
+            // out twice.
+            let rediscovered = |p: T| roots.iter().any(|known| (p - known.root).abs() <= options.newton.x_abs_tol + options.newton.x_rel_tol*p.abs());
+            if let Ok(report) = newton_solve(|x| f.eval(x), root, polish_options) && report.residual < residual && !rediscovered(report.root) {
+                root = report.root;
+                residual = report.residual;
+                polished = Some(report);
+            }
+        }
+        if residual.is_nan() || residual > options.residual_tol {
+            failure = Some(NumlError::SpuriousRoot { x: root.value(), fx: residual.value() });
+            break;
+        }
+        roots.push(DeflatedRoot { root, residual, deflated, polished });
+    }
+
+    Ok(DeflationReport { roots, evaluations: f.evaluations, failure })
+}
+
+/// Summary of a successful multiple_root_newton() run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MultipleRootReport<T = f64> {
+    /// The usual root-finding summary.
+    pub report: RootReport<T>,
+
+    /// The detected multiplicity of the root: 1/u'(x) at the last iterate rounded to the nearest
+    /// positive integer, if it was within 0.25 of it and the last step used it. None if the last
+    /// step was a Newton step on u() instead, or if no step was taken.
+    pub multiplicity: Option<usize>,
+
+    /// The unrounded estimate 1/u'(x) of the multiplicity at the last iterate, or NaN if no step
+    /// was taken.
+    pub multiplicity_estimate: T,
+}
+
+/// Finds a root of f() with Newton's method adapted to roots of any multiplicity, using a
+/// derivative supplied by the caller.
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - df: impl FnMut(T) -> T
+/// - x0: T
+/// - options: NewtonOptions<T>
+///
+/// Near a root r of multiplicity m, f'(x) tends to zero along with f(x), and Newton's method only
+/// converges linearly, reducing the error by a factor of (m-1)/m per step. The function
+/// u(x) = f(x)/f'(x) behaves like (x - r)/m instead, so it has a simple root at r, and its
+/// derivative estimates the multiplicity as m = 1/u'(r). Each iteration computes u'(x) with
+/// derivative_mut(): if 1/u'(x) is within 0.25 of a positive integer m, it takes the modified
+/// Newton step x - m*f(x)/f'(x), and otherwise the step x - u(x)/u'(x) of Newton's method on u().
+/// Both converge quadratically whatever the multiplicity, and at a simple root the modified step
+/// is exactly Newton's step.
+///
+/// Rounding errors in f() limit the accuracy of a root of multiplicity m to about EPS^(1/m)
+/// relative to its size, so the default step tolerances can be out of reach. The iteration
+/// therefore also stops when a step no larger than EPS^(1/(2*m))*max(|x|, |typ|) fails to decrease
+/// |f|, returning the iterate with the smaller |f| and Termination::NoiseLimit. The other
+/// stopping criteria and options are those of newton_solve(). Each iteration costs three
+/// evaluations of f() and of df(), two of them for u'(x), plus one at x0; evaluations in the
+/// returned report counts evaluations of f().
+///
+/// Errors:
+/// - NumlError::TypError if options.typ is zero
+/// - NumlError::InvalidInput if options.typ is NaN or infinite
+/// - NumlError::NonFiniteValue if f(), f'() or u'() evaluates to NaN or an infinity
+/// - NumlError::DerivativeZeroError if f'(x) or u'(x) is exactly zero at an iterate which is not
+///   a root
+/// - NumlError::IllConditioned if a step exceeds the bound set by options.max_step
+/// - NumlError::Divergence if an iterate stops being finite
+/// - NumlError::NoConvergence if no stopping criterion is met within options.max_iterations
+///
+/// All errors that carry a derivative report f'(x).
+pub fn multiple_root_newton<T: Real>(mut f: impl FnMut(T) -> T, mut df: impl FnMut(T) -> T, x0: T, options: NewtonOptions<T>) -> Result<MultipleRootReport<T>, NumlError> {
+    check_typ(options.typ)?;
+    let mut f = Counted::new(move |x| [f(x), df(x)]);
+
+    let mut x = x0;
+    let [mut fx, mut dfx] = f.eval(x);
+    let mut multiplicity_estimate = T::NAN;
+    let mut multiplicity = None;
+    let mut iterations = 0;
+    loop {
+        if !(fx.is_finite() && dfx.is_finite()) {
+            return Err(NumlError::NonFiniteValue { x: x.value(), fx: fx.value(), derivative: dfx.value() });
+        }
+        let termination = if fx == T::ZERO {
+            Some(Termination::ExactRoot)
+        } else if fx.abs() <= options.f_tol {
+            Some(Termination::ResidualTolerance)
+        } else {
+            None
+        };
+        if let Some(termination) = termination {
+            let report = RootReport { root: x, iterations, evaluations: f.evaluations, residual: fx.abs(), termination };
+            return Ok(MultipleRootReport { report, multiplicity, multiplicity_estimate });
+        }
+        if dfx == T::ZERO {
+            return Err(NumlError::DerivativeZeroError);
+        }
+
+        let u = fx/dfx;
+        let du = derivative_mut(|t| {
+            let [ft, dft] = f.eval(t);
+            ft/dft
+        }, x, options.typ)?;
+        if iterations >= options.max_iterations {
+            return Err(NumlError::NoConvergence { iterations, x: x.value(), fx: fx.value(), derivative: dfx.value() });
+        }
+        if !du.is_finite() {
+            return Err(NumlError::NonFiniteValue { x: x.value(), fx: fx.value(), derivative: dfx.value() });
+        }
+        if du == T::ZERO {
+            return Err(NumlError::DerivativeZeroError);
+        }
+
+        multiplicity_estimate = T::ONE/du;
+        let rounded = multiplicity_estimate.value().round();
+        let step = if rounded >= 1.0 && (multiplicity_estimate - T::from_f64(rounded)).abs() <= T::from_f64(0.25) {
+            multiplicity = Some(rounded as usize);
+            T::from_f64(rounded)*u
+        } else {
+            multiplicity = None;
+            u/du
+        };
+        let scale = x.abs().max(options.typ.abs());
+        if step.abs() > options.max_step*scale {
+            return Err(NumlError::IllConditioned { x: x.value(), fx: fx.value(), derivative: dfx.value() });
+        }
+        let next = x - step;
+        if !next.is_finite() {
+            return Err(NumlError::Divergence { iterations, x: x.value() });
+        }
+        let [f_next, df_next] = f.eval(next);
+        iterations += 1;
+
+        let noise_step = T::EPSILON.powf(0.5/rounded.max(1.0))*scale;
+        if f_next.is_finite() && f_next.abs() >= fx.abs() && step.abs() <= noise_step {
+            let report = RootReport { root: x, iterations, evaluations: f.evaluations, residual: fx.abs(), termination: Termination::NoiseLimit };
+            return Ok(MultipleRootReport { report, multiplicity, multiplicity_estimate });
+        }
+        if f_next.is_finite() && step.abs() <= options.x_abs_tol + options.x_rel_tol*next.abs() {
+            let termination = if f_next == T::ZERO { Termination::ExactRoot } else { Termination::StepTolerance };
+            let report = RootReport { root: next, iterations, evaluations: f.evaluations, residual: f_next.abs(), termination };
+            return Ok(MultipleRootReport { report, multiplicity, multiplicity_estimate });
+        }
+
+        x = next;
+        fx = f_next;
+        dfx = df_next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_cubic(x: f64) -> f64 {
+        (x*x*x) + (2.0*x*x) - 0.4
+    }
+
+    #[test]
+    fn test_newton_solve() {
+        let report = newton_solve(sample_cubic, 1.0, NewtonOptions { typ: 0.5, ..Default::default() }).unwrap();
+        assert!(report.root > 0.4 && report.root < 0.41);
+        assert!(report.residual < 1e-14);
+        assert!(report.iterations < 12);
+        assert_eq!(report.evaluations, 1 + 3*report.iterations);
+    }
+
+    #[test]
+    fn test_newton_solve_counts_match_closure() {
+        let mut evaluations = 0;
+        let report = newton_solve(|x: f64| { evaluations += 1; x*x - 2.0 }, 1.0, NewtonOptions::default()).unwrap();
+        assert!((report.root - f64::sqrt(2.0)).abs() < 1e-15);
+        assert_eq!(report.evaluations, evaluations);
+    }
+
+    #[test]
+    fn test_newton_solve_residual_tolerance() {
+        let options = NewtonOptions { f_tol: 1e-6, ..Default::default() };
+        let report = newton_solve(|x: f64| x*x - 2.0, 1.0, options).unwrap();
+        assert_eq!(report.termination, Termination::ResidualTolerance);
+        assert!(report.residual <= 1e-6);
+    }
+
+    #[test]
+    fn test_newton_solve_exact_root() {
+        let report = newton_solve(|x: f64| x - 3.0, 3.0, NewtonOptions::default()).unwrap();
+        assert_eq!(report.termination, Termination::ExactRoot);
+        assert_eq!(report.iterations, 0);
+        assert_eq!(report.evaluations, 1);
+    }
+
+    #[test]
+    fn test_newton_solve_no_convergence() {
+        let options = NewtonOptions { max_iterations: 2, ..Default::default() };
+        let result = newton_solve(sample_cubic, 10.0, options);
+        match result {
+            Err(NumlError::NoConvergence { iterations, x, fx, derivative }) => {
+                assert_eq!(iterations, 2);
+                assert_eq!(fx, sample_cubic(x));
+                assert!((derivative - (3.0*x*x + 4.0*x)).abs() < 1e-6*derivative.abs());
+            }
+            other => panic!("expected NoConvergence, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_newton_solve_divergence() {
+        // Newton's method on cbrt() doubles the distance to the root on every step.
+        let options = NewtonOptions { max_iterations: 2000, ..Default::default() };
+        let result = newton_solve(f64::cbrt, 1.0, options);
+        assert!(matches!(result, Err(NumlError::Divergence { .. })));
+    }
+
+    #[test]
+    fn test_newton_solve_derivative_zero() {
+        let result = newton_solve(|x: f64| x*x - 1.0, 0.0, NewtonOptions::default());
+        assert!(matches!(result, Err(NumlError::DerivativeZeroError)));
+    }
+
+    #[test]
+    fn test_newton_solve_f32() {
+        let report = newton_solve(|x: f32| x*x - 2.0, 1.0, NewtonOptions::default()).unwrap();
+        assert!((report.root - f32::sqrt(2.0)).abs() <= f32::EPSILON);
+    }
+
+    #[test]
+    fn test_newton_solve_ill_conditioned() {
+        // The derivative vanishes at the minimum of x^2 + 1, so the step from close to it is huge.
+        let result = newton_solve(|x: f64| x*x + 1.0, 1e-9, NewtonOptions::default());
+        assert!(matches!(result, Err(NumlError::IllConditioned { x, fx, derivative }) if x == 1e-9 && fx == 1.0 && derivative.abs() < 1e-8));
+        let options = NewtonOptions { max_step: 10.0, ..Default::default() };
+        let result = newton_solve(|x: f64| x*x - 1.0, 0.01, options);
+        assert!(matches!(result, Err(NumlError::IllConditioned { .. })));
+    }
+
+    #[test]
+    fn test_newton_solve_non_finite_value() {
+        let result = newton_solve(f64::ln, -1.0, NewtonOptions::default());
+        assert!(matches!(result, Err(NumlError::NonFiniteValue { x, fx, .. }) if x == -1.0 && fx.is_nan()));
+        // The first step from 3 lands at a negative x, where sqrt() is NaN.
+        let result = newton_solve(|x: f64| x.sqrt() - 0.1, 3.0, NewtonOptions::default());
+        assert!(matches!(result, Err(NumlError::NonFiniteValue { x, .. }) if x < 0.0));
+    }
+
+    #[test]
+    fn test_newton_solve_invalid_typ() {
+        let options = NewtonOptions { typ: f64::NAN, ..Default::default() };
+        let result = newton_solve(|x: f64| x*x - 2.0, 1.0, options);
+        assert!(matches!(result, Err(NumlError::InvalidInput { name: "typ", .. })));
+    }
+
+    fn sample_cubic_derivatives(x: f64) -> [f64; 4] {
+        [sample_cubic(x), 3.0*x*x + 4.0*x, 6.0*x + 4.0, 6.0]
+    }
+
+    #[test]
+    fn test_householder_orders() {
+        let options = NewtonOptions { x_abs_tol: 0.0, x_rel_tol: 0.0, f_tol: 1e-15, ..Default::default() };
+        let newton = newton(sample_cubic, |x| 3.0*x*x + 4.0*x, 3.0, options).unwrap();
+        let halley = halley(sample_cubic, |x| 3.0*x*x + 4.0*x, |x| 6.0*x + 4.0, 3.0, options).unwrap();
+        let third = householder(sample_cubic_derivatives, 3.0, options).unwrap();
+        for report in [newton, halley, third] {
+            assert!(report.root > 0.4 && report.root < 0.41);
+            assert!(report.residual <= 1e-15);
+            assert_eq!(report.evaluations, report.iterations + 1);
+        }
+        assert!(halley.iterations < newton.iterations);
+        assert!(third.iterations <= halley.iterations);
+    }
+
+    #[test]
+    fn test_householder_matches_closed_forms() {
+        let values = sample_cubic_derivatives(0.7);
+        let [f, df, d2f, _] = values;
+        assert!((householder_step(&[f, df]) + f/df).abs() < 1e-15);
+        assert!((householder_step(&[f, df, d2f]) + 2.0*f*df/(2.0*df*df - f*d2f)).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_newton_fewer_evaluations_than_newton_solve() {
+        let analytic = newton(sample_cubic, |x| 3.0*x*x + 4.0*x, 1.0, NewtonOptions::default()).unwrap();
+        let numerical = newton_solve(sample_cubic, 1.0, NewtonOptions { typ: 0.5, ..Default::default() }).unwrap();
+        assert!((analytic.root - numerical.root).abs() < 1e-15);
+        assert!(analytic.evaluations < numerical.evaluations);
+    }
+
+    #[test]
+    fn test_householder_errors() {
+        let result = householder(|x: f64| [x], 1.0, NewtonOptions::default());
+        assert!(matches!(result, Err(NumlError::InvalidInput { name: "order", .. })));
+        let result = newton(|x: f64| x*x - 1.0, |x| 2.0*x, 0.0, NewtonOptions::default());
+        assert!(matches!(result, Err(NumlError::DerivativeZeroError)));
+        let result = halley(|x: f64| x.ln(), |x| 1.0/x, |x| -1.0/(x*x), -1.0, NewtonOptions::default());
+        assert!(matches!(result, Err(NumlError::NonFiniteValue { x: -1.0, .. })));
+        let options = NewtonOptions { max_iterations: 1, ..Default::default() };
+        let result = newton(sample_cubic, |x| 3.0*x*x + 4.0*x, 10.0, options);
+        assert!(matches!(result, Err(NumlError::NoConvergence { iterations: 1, .. })));
+    }
+
+    #[test]
+    fn test_damped_newton_globalises() {
+        // Plain Newton's method on atan() diverges from x0 = 2.
+        assert!(newton_solve(f64::atan, 2.0, NewtonOptions::default()).is_err());
+        let damped = damped_newton(f64::atan, 2.0, DampedNewtonOptions::default()).unwrap();
+        assert!(damped.report.root.abs() < 1e-15);
+        assert!(damped.reductions > 0);
+    }
+
+    #[test]
+    fn test_damped_newton_full_steps_near_root() {
+        let options = DampedNewtonOptions { newton: NewtonOptions { typ: 0.5, ..Default::default() }, ..Default::default() };
+        let damped = damped_newton(sample_cubic, 1.0, options).unwrap();
+        let plain = newton_solve(sample_cubic, 1.0, options.newton).unwrap();
+        assert_eq!(damped.reductions, 0);
+        assert_eq!(damped.report, plain);
+    }
+
+    #[test]
+    fn test_damped_newton_domain() {
+        // The full first step lands at a negative x, where sqrt() is NaN.
+        let damped = damped_newton(|x: f64| x.sqrt() - 0.1, 3.0, DampedNewtonOptions::default()).unwrap();
+        assert!((damped.report.root - 0.01).abs() < 1e-15);
+        assert!(damped.reductions > 0);
+    }
+
+    #[test]
+    fn test_damped_newton_line_search_failed() {
+        // |f| has a local minimum at the jump at 0, which is not a root.
+        let jump = |x: f64| if x < 0.0 { x - 1.0 } else { x + 1.0 };
+        let result = damped_newton(jump, 1.0, DampedNewtonOptions::default());
+        assert!(matches!(result, Err(NumlError::LineSearchFailed { x, fx, .. }) if x.abs() < 1e-9 && (fx - 1.0).abs() < 1e-9));
+        // A larger minimum step fraction gives up sooner.
+        let options = DampedNewtonOptions { min_step: 0.75, ..Default::default() };
+        let result = damped_newton(f64::atan, 2.0, options);
+        assert!(matches!(result, Err(NumlError::LineSearchFailed { x: 2.0, .. })));
+        let options = DampedNewtonOptions { min_step: 0.0, ..Default::default() };
+        assert!(matches!(damped_newton(f64::atan, 2.0, options), Err(NumlError::InvalidInput { name: "min_step", .. })));
+        for armijo in [-0.5, 0.0, 1.0, f64::NAN] {
+            let options = DampedNewtonOptions { armijo, ..Default::default() };
+            assert!(matches!(damped_newton(f64::atan, 2.0, options), Err(NumlError::InvalidInput { name: "armijo", .. })));
+        }
+    }
+
+    #[test]
+    fn test_deflated_newton_finds_distinct_roots() {
+        let report = deflated_newton(sample_cubic, 1.0, 3, DeflationOptions::default()).unwrap();
+        assert!(report.failure.is_none());
+        let mut roots: Vec<f64> = report.roots.iter().map(|r| r.root).collect();
+        roots.sort_by(f64::total_cmp);
+        assert!(roots[0] < -1.8 && roots[1] < -0.5 && roots[1] > -0.6 && roots[2] > 0.4 && roots[2] < 0.41);
+        assert!(report.roots.iter().all(|r| r.residual < 1e-15));
+        // Newton from the same starting point without deflation always finds the same root.
+        let again = newton_solve(sample_cubic, 1.0, NewtonOptions::default()).unwrap();
+        assert_eq!(again.root, report.roots[0].root);
+    }
+
+    #[test]
+    fn test_deflated_newton_polish() {
+        let newton = NewtonOptions { f_tol: 1e-6, ..Default::default() };
+        let rough = deflated_newton(sample_cubic, 1.0, 3, DeflationOptions { newton, polish: false, residual_tol: 1e-6 }).unwrap();
+        let polished = deflated_newton(sample_cubic, 1.0, 3, DeflationOptions { newton, ..Default::default() }).unwrap();
+        assert_eq!(rough.roots.len(), 3);
+        assert_eq!(polished.roots.len(), 3);
+        assert!(rough.roots.iter().all(|r| r.polished.is_none() && r.residual <= 1e-6));
+        assert!(polished.roots.iter().all(|r| r.residual < 1e-15));
+        assert!(polished.roots.iter().any(|r| r.polished.is_some()));
+    }
+
+    #[test]
+    fn test_deflated_newton_close_roots() {
+        // With a loose residual tolerance, the second search stops near 1.00125, from where
+        // polishing slides back onto the root at 1.0001 that the first search already found.
+        let f = |x: f64| (x - 1.0)*(x - 1.0001)*(x + 2.0);
+        let options = DeflationOptions { newton: NewtonOptions { f_tol: 1e-2, ..Default::default() }, residual_tol: 1e-3, ..Default::default() };
+        let report = deflated_newton(f, 1.5, 3, options).unwrap();
+        let mut roots: Vec<f64> = report.roots.iter().map(|r| r.root).collect();
+        roots.sort_by(f64::total_cmp);
+        assert_eq!(roots.len(), 3);
+        assert!(roots.windows(2).all(|pair| pair[1] - pair[0] > 1e-5));
+        assert!((report.roots[0].root - 1.0001).abs() < 1e-15);
+        assert!(report.roots[1].polished.is_none());
+        assert_eq!(report.roots[1].root, report.roots[1].deflated.root);
+    }
+
+    #[test]
+    fn test_deflated_newton_stops() {
+        // Once the only root is divided out, the deflated function is constant.
+        let report = deflated_newton(|x: f64| x - 1.0, 0.0, 2, DeflationOptions::default()).unwrap();
+        assert_eq!(report.roots.len(), 1);
+        assert!(matches!(report.failure, Some(NumlError::DerivativeZeroError)));
+
+        // (exp(x) - 2)/(x - ln(2)) tends to zero as x goes to minus infinity.
+        let options = DeflationOptions { newton: NewtonOptions { f_tol: 1e-2, ..Default::default() }, ..Default::default() };
+        let report = deflated_newton(|x: f64| x.exp() - 2.0, 0.0, 2, options).unwrap();
+        assert_eq!(report.roots.len(), 1);
+        assert!((report.roots[0].root - 2f64.ln()).abs() < 1e-15);
+        assert!(matches!(report.failure, Some(NumlError::SpuriousRoot { x, .. }) if x < -100.0));
+
+        let options = DeflationOptions { residual_tol: -1.0, ..Default::default() };
+        assert!(matches!(deflated_newton(sample_cubic, 1.0, 3, options), Err(NumlError::InvalidInput { name: "residual_tol", .. })));
+    }
+
+    #[test]
+    fn test_multiple_root_newton_double() {
+        // (x - 1)^2*(x + 2), expanded so that it carries rounding errors near the double root.
+        let f = |x: f64| x*x*x - 3.0*x + 2.0;
+        let df = |x: f64| 3.0*x*x - 3.0;
+        let report = multiple_root_newton(f, df, 3.0, NewtonOptions::default()).unwrap();
+        assert_eq!(report.multiplicity, Some(2));
+        assert!((report.multiplicity_estimate - 2.0).abs() < 0.01);
+        assert!((report.report.root - 1.0).abs() < 1e-7);
+        assert!(report.report.iterations < 10);
+        assert_eq!(report.report.evaluations, 1 + 3*report.report.iterations);
+
+        // Plain Newton only gains about a bit per iteration.
+        let plain = newton(f, df, 3.0, NewtonOptions { max_iterations: 30, ..Default::default() });
+        assert!(!matches!(plain, Ok(report) if report.iterations < 25));
+    }
+
+    #[test]
+    fn test_multiple_root_newton_triple() {
+        let f = |x: f64| ((x - 3.0)*x + 3.0)*x - 1.0;
+        let df = |x: f64| (3.0*x - 6.0)*x + 3.0;
+        let report = multiple_root_newton(f, df, 2.0, NewtonOptions::default()).unwrap();
+        assert_eq!(report.multiplicity, Some(3));
+        assert!((report.report.root - 1.0).abs() < 1e-4);
+
+        // Without rounding errors the root is found exactly.
+        let report = multiple_root_newton(|x: f64| (x - 0.5).powi(3), |x: f64| 3.0*(x - 0.5).powi(2), 2.0, NewtonOptions::default()).unwrap();
+        assert_eq!(report.multiplicity, Some(3));
+        assert!((report.report.root - 0.5).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_multiple_root_newton_unaccepted_estimate() {
+        // sqrt(x)*x behaves like a root of multiplicity 1.5, which is never rounded.
+        let f = |x: f64| x*x.abs().sqrt();
+        let df = |x: f64| 1.5*x.abs().sqrt();
+        let report = multiple_root_newton(f, df, 1.0, NewtonOptions::default()).unwrap();
+        assert_eq!(report.multiplicity, None);
+        assert!((report.multiplicity_estimate - 1.5).abs() < 0.01);
+        assert!(report.report.root.abs() < 1e-8);
+
+        let report = multiple_root_newton(f, df, 0.0, NewtonOptions::default()).unwrap();
+        assert_eq!(report.multiplicity, None);
+        assert!(report.multiplicity_estimate.is_nan());
+    }
+
+    #[test]
+    fn test_multiple_root_newton_simple() {
+        let df = |x: f64| 3.0*x*x + 4.0*x;
+        let report = multiple_root_newton(sample_cubic, df, 1.0, NewtonOptions::default()).unwrap();
+        assert_eq!(report.multiplicity, Some(1));
+        assert!(report.report.root > 0.4 && report.report.root < 0.41);
+        assert!(report.report.residual < 1e-15);
+    }
+}
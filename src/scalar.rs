@@ -0,0 +1,206 @@
+//! The Scalar trait, for writing functions that numl can evaluate with different number types.
+
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// A real number type supporting arithmetic and the common elementary functions.
+///
+/// Writing a function generic over Scalar, for example
+///
+/// ```
+/// use numl::Scalar;
+///
+/// fn cubic<T: Scalar>(x: T) -> T {
+///     x*x*x + T::from_f64(2.0)*x*x - T::from_f64(0.4)
+/// }
+/// ```
+///
+/// allows the same function to be evaluated with f64 or f32 for its value and with Dual for its
+/// exact derivative (see derivative_ad()). Constants have to be converted with from_f64(), since
+/// mixed arithmetic with f64 is not part of the trait.
+///
+/// Comparisons only look at the real value, so branching on them gives the derivative of the
+/// branch that was taken.
+pub trait Scalar:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    /// Converts a constant into this type.
+    fn from_f64(value: f64) -> Self;
+
+    /// Returns the real value of this number, discarding any derivative information.
+    fn value(self) -> f64;
+
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn cbrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn powf(self, p: f64) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan(self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn tanh(self) -> Self;
+}
+
+impl Scalar for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn value(self) -> f64 {
+        self
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn cbrt(self) -> Self {
+        f64::cbrt(self)
+    }
+
+    fn exp(self) -> Self {
+        f64::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        f64::ln(self)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        f64::powi(self, n)
+    }
+
+    fn powf(self, p: f64) -> Self {
+        f64::powf(self, p)
+    }
+
+    fn sin(self) -> Self {
+        f64::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        f64::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        f64::tan(self)
+    }
+
+    fn asin(self) -> Self {
+        f64::asin(self)
+    }
+
+    fn acos(self) -> Self {
+        f64::acos(self)
+    }
+
+    fn atan(self) -> Self {
+        f64::atan(self)
+    }
+
+    fn sinh(self) -> Self {
+        f64::sinh(self)
+    }
+
+    fn cosh(self) -> Self {
+        f64::cosh(self)
+    }
+
+    fn tanh(self) -> Self {
+        f64::tanh(self)
+    }
+}
+
+impl Scalar for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn value(self) -> f64 {
+        self as f64
+    }
+
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+
+    fn cbrt(self) -> Self {
+        f32::cbrt(self)
+    }
+
+    fn exp(self) -> Self {
+        f32::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        f32::ln(self)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        f32::powi(self, n)
+    }
+
+    fn powf(self, p: f64) -> Self {
+        f32::powf(self, p as f32)
+    }
+
+    fn sin(self) -> Self {
+        f32::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        f32::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        f32::tan(self)
+    }
+
+    fn asin(self) -> Self {
+        f32::asin(self)
+    }
+
+    fn acos(self) -> Self {
+        f32::acos(self)
+    }
+
+    fn atan(self) -> Self {
+        f32::atan(self)
+    }
+
+    fn sinh(self) -> Self {
+        f32::sinh(self)
+    }
+
+    fn cosh(self) -> Self {
+        f32::cosh(self)
+    }
+
+    fn tanh(self) -> Self {
+        f32::tanh(self)
+    }
+}
@@ -0,0 +1,429 @@
+//! Finding all roots of a function in an interval by scanning it for sign changes and dips.
+
+use crate::bracket::check_tol;
+use crate::roots::Counted;
+use crate::{brent, NumlError, Real};
+
+/// How find_roots() places its sample points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sampling {
+    /// Samples at subdivisions+1 equally spaced points.
+    Uniform,
+
+    /// Starts from the uniform samples and recursively bisects every subinterval on which f()
+    /// looks far from linear, or dips below the values at both ends, up to max_depth times.
+    /// This spends samples where roots are likely to hide, such as near pairs of close roots.
+    Adaptive,
+}
+
+/// Options controlling find_roots().
+///
+/// subdivisions is the number of equal subintervals [a, b] is first divided into, and sampling
+/// and max_depth control whether and how far these are refined (see Sampling). Each root is
+/// refined with brent() to a bracket width of tol, which can be zero for full precision.
+///
+/// A local minimum of |f| without a sign change is reported as a touching root when the minimum
+/// of |f| found there is at most touch_tol times the largest finite |f| seen while sampling.
+///
+/// The defaults are 100 uniform subdivisions, max_depth = 8, tol = 0 and touch_tol = sqrt(EPS).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScanOptions<T = f64> {
+    pub subdivisions: usize,
+    pub sampling: Sampling,
+    pub max_depth: usize,
+    pub tol: T,
+    pub touch_tol: T,
+}
+
+impl<T: Real> Default for ScanOptions<T> {
+    fn default() -> Self {
+        ScanOptions {
+            subdivisions: 100,
+            sampling: Sampling::Uniform,
+            max_depth: 8,
+            tol: T::ZERO,
+            touch_tol: T::EPSILON.sqrt(),
+        }
+    }
+}
+
+/// How a root found by find_roots() was detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootKind {
+    /// f() changes sign at the root, which was located to full precision by brent().
+    SignChange,
+
+    /// f() touches zero without changing sign, as at a double root. The root is the minimiser of
+    /// |f| and is typically only accurate to about sqrt(EPS).
+    Touching,
+}
+
+/// A root found by find_roots(), with diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScannedRoot<T = f64> {
+    /// The computed root.
+    pub root: T,
+
+    /// |f(root)|.
+    pub residual: T,
+
+    /// How the root was detected.
+    pub kind: RootKind,
+
+    /// The interval between sample points in which the root was detected.
+    pub bracket: (T, T),
+
+    /// Number of evaluations of f() spent on refining this root, not counting the sampling.
+    pub evaluations: usize,
+}
+
+/// Summary of a find_roots() run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanReport<T = f64> {
+    /// The roots found, sorted in increasing order.
+    pub roots: Vec<ScannedRoot<T>>,
+
+    /// Number of sample points that were evaluated.
+    pub samples: usize,
+
+    /// Number of sign changes which turned out to be poles rather than roots, and were dropped.
+    pub poles: usize,
+
+    /// Number of brackets which were dropped because brent() failed on them, usually because f()
+    /// is NaN somewhere inside.
+    pub skipped: usize,
+
+    /// Total number of evaluations of f().
+    pub evaluations: usize,
+}
+
+/// Finds all roots of f() in the interval [a, b].
+///
+/// Inputs:
+/// - f: impl FnMut(T) -> T
+/// - a: T
+/// - b: T
+/// - options: ScanOptions<T>
+///
+/// f() is sampled on [a, b] as set by options.subdivisions and options.sampling. Every pair of
+/// neighbouring samples with opposite signs brackets a root, which is refined with brent(), and
+/// every sample at which f() is exactly zero is a root. Every sample where |f| is smaller than at
+/// both neighbours, without a sign change, is investigated with a golden section search for the
+/// minimum of |f|: if that search finds a value of the opposite sign, the two roots on either side
+/// of it are refined with brent(), and otherwise the minimum is reported as a touching root if it
+/// is small enough (see ScanOptions).
+///
+/// Sign changes across a pole, as for tan() or 1/x, also produce a bracket, but brent() then
+/// converges to the pole where |f| is huge. Those are recognised by |f| at the result exceeding
+/// |f| at both ends of the bracket, and are dropped and counted in the report instead. Samples
+/// where f() is NaN are skipped, and so is any bracket on which brent() fails, for example because
+/// f() is NaN inside it; such brackets are counted in the report, and the scan carries on with the
+/// others. Roots closer together than tol + 4*EPS*|root| (or
+/// sqrt(EPS)*(b - a) if one of them is touching) are merged, keeping the one with the smaller
+/// residual.
+///
+/// Roots closer together than the sample spacing with no dip of |f| between them, and touching
+/// roots between two samples that do not produce a local minimum of the samples, can be missed;
+/// increase options.subdivisions or use Sampling::Adaptive to find them.
+///
+/// A NumlError::InvalidInput is returned if a or b is not finite, if a >= b, if
+/// options.subdivisions is zero or if options.tol is negative or NaN.
+pub fn find_roots<T: Real>(f: impl FnMut(T) -> T, a: T, b: T, options: ScanOptions<T>) -> Result<ScanReport<T>, NumlError> {
+    if !a.is_finite() {
+        return Err(NumlError::InvalidInput { name: "a", value: a.value() });
+    }
+    if !(b.is_finite() && b > a) {
+        return Err(NumlError::InvalidInput { name: "b", value: b.value() });
+    }
+    if options.subdivisions == 0 {
+        return Err(NumlError::InvalidInput { name: "subdivisions", value: 0.0 });
+    }
+    check_tol(options.tol)?;
+    let mut f = Counted::new(f);
+
+    // Sample points, in increasing order.
+    let n = options.subdivisions;
+    let mut xs = Vec::with_capacity(n + 1);
+    let mut fs = Vec::with_capacity(n + 1);
+    for i in 0..=n {
+        let x = if i == n { b } else { a + (b - a)*T::from_f64(i as f64/n as f64) };
+        xs.push(x);
+        fs.push(f.eval(x));
+    }
+    let max_abs = |fs: &[T]| fs.iter().copied().filter(|v| v.is_finite()).fold(T::ZERO, |m, v| m.max(v.abs()));
+    if let Sampling::Adaptive = options.sampling {
+        let scale = max_abs(&fs);
+        let (uniform_xs, uniform_fs) = (xs, fs);
+        xs = vec![uniform_xs[0]];
+        fs = vec![uniform_fs[0]];
+        for i in 1..=n {
+            refine_samples(&mut f, (uniform_xs[i-1], uniform_fs[i-1]), (uniform_xs[i], uniform_fs[i]), scale, options.max_depth, &mut xs, &mut fs);
+            xs.push(uniform_xs[i]);
+            fs.push(uniform_fs[i]);
+        }
+    }
+    let samples = f.evaluations;
+    let scale = max_abs(&fs);
+
+    let mut found = Found { roots: Vec::new(), poles: 0, skipped: 0 };
+    for i in 0..xs.len() {
+        let (x, fx) = (xs[i], fs[i]);
+        if fx == T::ZERO {
+            let changes_sign = i > 0 && i + 1 < xs.len() && (fs[i-1] < T::ZERO) != (fs[i+1] < T::ZERO);
+            let kind = if changes_sign { RootKind::SignChange } else { RootKind::Touching };
+            found.roots.push(ScannedRoot { root: x, residual: T::ZERO, kind, bracket: (x, x), evaluations: 0 });
+            continue;
+        }
+        if fx.is_nan() {
+            continue;
+        }
+
+        if i + 1 < xs.len() && fs[i+1] != T::ZERO && !fs[i+1].is_nan() && (fx < T::ZERO) != (fs[i+1] < T::ZERO) {
+            let refined = refine_sign_change(&mut f, x, fx, xs[i+1], fs[i+1], options.tol);
+            found.add(refined, (x, xs[i+1]));
+        }
+
+        if i > 0 && i + 1 < xs.len() {
+            let (f_left, f_right) = (fs[i-1], fs[i+1]);
+            let same_sign = f_left != T::ZERO && f_right != T::ZERO && (f_left < T::ZERO) == (fx < T::ZERO) && (f_right < T::ZERO) == (fx < T::ZERO);
+            if same_sign && fx.abs() < f_left.abs() && fx.abs() <= f_right.abs() {
+                search_dip(&mut f, (xs[i-1], f_left), (x, fx), (xs[i+1], f_right), scale, options, &mut found);
+            }
+        }
+    }
+
+    let Found { mut roots, poles, skipped } = found;
+    roots.sort_by(|r, s| r.root.partial_cmp(&s.root).unwrap_or(std::cmp::Ordering::Equal));
+    let mut merged: Vec<ScannedRoot<T>> = Vec::with_capacity(roots.len());
+    for root in roots {
+        if let Some(last) = merged.last_mut() {
+            let touching = last.kind == RootKind::Touching || root.kind == RootKind::Touching;
+            let distance = if touching {
+                T::EPSILON.sqrt()*(b - a)
+            } else {
+                options.tol + T::from_f64(4.0)*T::EPSILON*last.root.abs().max(root.root.abs())
+            };
+            if root.root - last.root <= distance {
+                if root.residual < last.residual {
+                    *last = root;
+                }
+                continue;
+            }
+        }
+        merged.push(root);
+    }
+
+    Ok(ScanReport { roots: merged, samples, poles, skipped, evaluations: f.evaluations })
+}
+
+/// The outcome of refining one bracket.
+enum Refined<T> {
+    Root(ScannedRoot<T>),
+    Pole,
+    Failed,
+}
+
+/// The roots found so far, and the brackets dropped along the way.
+struct Found<T> {
+    roots: Vec<ScannedRoot<T>>,
+    poles: usize,
+    skipped: usize,
+}
+
+impl<T: Real> Found<T> {
+    /// Records the outcome of refining a bracket, reporting a root as detected in bracket.
+    fn add(&mut self, refined: Refined<T>, bracket: (T, T)) {
+        match refined {
+            Refined::Root(root) => self.roots.push(ScannedRoot { bracket, ..root }),
+            Refined::Pole => self.poles += 1,
+            Refined::Failed => self.skipped += 1,
+        }
+    }
+}
+
+/// Inserts extra samples strictly between left and right, in increasing order, wherever f() looks
+/// far from linear compared to scale, or dips below both ends.
+fn refine_samples<T: Real, F: FnMut(T) -> T>(f: &mut Counted<F>, (left, f_left): (T, T), (right, f_right): (T, T), scale: T, depth: usize, xs: &mut Vec<T>, fs: &mut Vec<T>) {
+    if depth == 0 {
+        return;
+    }
+    let half = T::from_f64(0.5);
+    let m = left + half*(right - left);
+    if m <= left || m >= right {
+        return;
+    }
+    let fm = f.eval(m);
+    let linear = half*(f_left + f_right);
+    let dips = fm.abs() < f_left.abs().min(f_right.abs());
+    let curved = (fm - linear).abs() > T::from_f64(0.1)*scale;
+    if dips || curved || !fm.is_finite() {
+        refine_samples(f, (left, f_left), (m, fm), scale, depth - 1, xs, fs);
+        xs.push(m);
+        fs.push(fm);
+        refine_samples(f, (m, fm), (right, f_right), scale, depth - 1, xs, fs);
+    } else {
+        xs.push(m);
+        fs.push(fm);
+    }
+}
+
+/// Refines a sign change with brent(), recognising poles and brackets brent() fails on.
+fn refine_sign_change<T: Real, F: FnMut(T) -> T>(f: &mut Counted<F>, left: T, f_left: T, right: T, f_right: T, tol: T) -> Refined<T> {
+    let Ok(report) = brent(|t| f.eval(t), left, right, tol) else {
+        return Refined::Failed;
+    };
+    if report.residual > f_left.abs().max(f_right.abs()) {
+        return Refined::Pole;
+    }
+    Refined::Root(ScannedRoot { root: report.root, residual: report.residual, kind: RootKind::SignChange, bracket: (left, right), evaluations: report.evaluations })
+}
+
+/// Minimises |f| over [left, right] around a dip at middle with a golden section search. If a
+/// value of the opposite sign turns up, the roots on both sides of it are refined instead.
+fn search_dip<T: Real, F: FnMut(T) -> T>(f: &mut Counted<F>, left: (T, T), middle: (T, T), right: (T, T), scale: T, options: ScanOptions<T>, found: &mut Found<T>) {
+    let start = f.evaluations;
+    let negative = middle.1 < T::ZERO;
+    let golden = T::from_f64(0.381966011250105);
+    let tol = T::EPSILON.sqrt();
+
+    let (mut low, mut high) = (left.0, right.0);
+    let (mut best, mut f_best) = middle;
+    for _ in 0..200 {
+        if high - low <= tol*best.abs() + options.tol + T::MIN_POSITIVE {
+            break;
+        }
+        // Probe the larger side of best, the golden section fraction away from it.
+        let x = if high - best > best - low { best + golden*(high - best) } else { best - golden*(best - low) };
+        let fx = f.eval(x);
+        if fx.is_nan() {
+            break;
+        }
+        if fx == T::ZERO || (fx < T::ZERO) != negative {
+            if fx == T::ZERO {
+                found.roots.push(ScannedRoot { root: x, residual: T::ZERO, kind: RootKind::Touching, bracket: (left.0, right.0), evaluations: f.evaluations - start });
+                return;
+            }
+            // Two roots close together: one on each side of x.
+            for (l, fl, r, fr) in [(left.0, left.1, x, fx), (x, fx, right.0, right.1)] {
+                let refined = refine_sign_change(f, l, fl, r, fr, options.tol);
+                found.add(refined, (left.0, right.0));
+            }
+            return;
+        }
+        if fx.abs() < f_best.abs() {
+            if x < best { high = best } else { low = best }
+            best = x;
+            f_best = fx;
+        } else if x < best {
+            low = x;
+        } else {
+            high = x;
+        }
+    }
+
+    if f_best.abs() <= options.touch_tol*scale {
+        found.roots.push(ScannedRoot { root: best, residual: f_best.abs(), kind: RootKind::Touching, bracket: (left.0, right.0), evaluations: f.evaluations - start });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots_of(report: &ScanReport) -> Vec<f64> {
+        report.roots.iter().map(|r| r.root).collect()
+    }
+
+    #[test]
+    fn test_find_roots_sin() {
+        let report = find_roots(f64::sin, -1.0, 10.0, ScanOptions::default()).unwrap();
+        let roots = roots_of(&report);
+        assert_eq!(roots.len(), 4);
+        for (root, k) in roots.iter().zip(0..) {
+            assert!((root - k as f64*std::f64::consts::PI).abs() < 1e-14);
+        }
+        assert!(report.roots.iter().all(|r| r.kind == RootKind::SignChange));
+        assert_eq!(report.samples, 101);
+    }
+
+    #[test]
+    fn test_find_roots_sample_cubic() {
+        let cubic = |x: f64| x*x*x + 2.0*x*x - 0.4;
+        let report = find_roots(cubic, -3.0, 3.0, ScanOptions::default()).unwrap();
+        assert_eq!(report.roots.len(), 3);
+        for root in &report.roots {
+            assert!(cubic(root.root).abs() < 1e-14);
+            assert!(root.bracket.0 <= root.root && root.root <= root.bracket.1);
+        }
+        assert!(report.roots[2].root > 0.4 && report.roots[2].root < 0.41);
+    }
+
+    #[test]
+    fn test_find_roots_touching() {
+        // A double root at 0.31 and a simple root at 0.7.
+        let f = |x: f64| (x - 0.31)*(x - 0.31)*(x - 0.7);
+        let report = find_roots(f, 0.0, 1.0, ScanOptions::default()).unwrap();
+        assert_eq!(report.roots.len(), 2);
+        assert_eq!(report.roots[0].kind, RootKind::Touching);
+        assert!((report.roots[0].root - 0.31).abs() < 1e-7);
+        assert_eq!(report.roots[1].kind, RootKind::SignChange);
+        assert!((report.roots[1].root - 0.7).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_find_roots_close_pair() {
+        // Two roots 1e-3 apart, between the same pair of uniform samples.
+        let f = |x: f64| (x - 0.3141)*(x - 0.3151) + 1e-9;
+        let report = find_roots(f, 0.0, 1.0, ScanOptions { subdivisions: 10, ..Default::default() }).unwrap();
+        assert_eq!(report.roots.len(), 2);
+        assert!(report.roots.iter().all(|r| f(r.root).abs() < 1e-15));
+    }
+
+    #[test]
+    fn test_find_roots_adaptive() {
+        // Roots of sin(1/x) crowd towards zero; adaptive sampling finds more of them with the
+        // same number of initial subdivisions.
+        let f = |x: f64| (1.0/x).sin();
+        let options = ScanOptions { subdivisions: 50, ..Default::default() };
+        let uniform = find_roots(f, 0.02, 1.0, options).unwrap();
+        let adaptive = find_roots(f, 0.02, 1.0, ScanOptions { sampling: Sampling::Adaptive, ..options }).unwrap();
+        // The roots are 1/(k*pi) for k = 1, ..., 15.
+        assert_eq!(adaptive.roots.len(), 15);
+        assert!(uniform.roots.len() < adaptive.roots.len());
+        for root in &adaptive.roots {
+            let k = (1.0/(root.root*std::f64::consts::PI)).round();
+            assert!((root.root - 1.0/(k*std::f64::consts::PI)).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_find_roots_poles_and_exact_samples() {
+        let report = find_roots(f64::tan, 0.5, 5.0, ScanOptions::default()).unwrap();
+        assert_eq!(roots_of(&report).len(), 1);
+        assert!((report.roots[0].root - std::f64::consts::PI).abs() < 1e-15);
+        assert_eq!(report.poles, 2);
+
+        let report = find_roots(|x: f64| x*(x - 0.5), -1.0, 1.0, ScanOptions::default()).unwrap();
+        assert_eq!(roots_of(&report), vec![0.0, 0.5]);
+        assert!(report.roots.iter().all(|r| r.evaluations == 0 && r.kind == RootKind::SignChange));
+    }
+
+    #[test]
+    fn test_find_roots_nan_inside_bracket() {
+        // NaN on (0.552, 0.558), strictly between the samples at 0.55 and 0.56, around the root at
+        // 0.555; the root at 0.2 must still be found.
+        let f = |x: f64| if x > 0.552 && x < 0.558 { f64::NAN } else { (x - 0.555)*(x - 0.2) };
+        let report = find_roots(f, 0.0, 1.0, ScanOptions::default()).unwrap();
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.roots.len(), 1);
+        assert!((report.roots[0].root - 0.2).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_find_roots_errors() {
+        let f = |x: f64| x;
+        assert!(matches!(find_roots(f, 1.0, 0.0, ScanOptions::default()), Err(NumlError::InvalidInput { name: "b", .. })));
+        let options = ScanOptions { subdivisions: 0, ..Default::default() };
+        assert!(matches!(find_roots(f, 0.0, 1.0, options), Err(NumlError::InvalidInput { name: "subdivisions", .. })));
+    }
+}
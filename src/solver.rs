@@ -0,0 +1,600 @@
+//! A common interface for the one-dimensional root finders, and the methods which are only
+//! available through it: the secant method, the regula falsi variants, Ridders' method and
+//! Newton's method safeguarded by bisection.
+
+use crate::bracket::{check_bracket, check_tol, check_value, midpoint, Bracket};
+use crate::diff::check_typ;
+use crate::roots::{newton_step, Counted};
+use crate::{bisect, brent, derivative_mut, itp, ItpOptions, NumlError, Real, RootReport, Termination};
+
+/// A method for finding a root of a function of one variable from two starting points.
+///
+/// Writing calling code against this trait allows the method to be swapped without changing
+/// anything else:
+///
+/// ```
+/// use numl::{Brent, Illinois, RootSolver};
+///
+/// fn solve_cubic(solver: &impl RootSolver) -> f64 {
+///     solver.solve(|x: f64| x*x*x + 2.0*x*x - 0.4, 0.0, 1.0).unwrap().root
+/// }
+///
+/// let a = solve_cubic(&Brent::default());
+/// let b = solve_cubic(&Illinois::default());
+/// assert!((a - b).abs() < 1e-15);
+/// ```
+///
+/// For the bracketing methods (Bisection, Brent, Itp, Illinois, Pegasus, AndersonBjorck, Ridders
+/// and SafeNewton) a and b are the ends of an interval over which f() changes sign, and may be
+/// passed in either order. For Secant they are merely two distinct initial guesses.
+pub trait RootSolver<T: Real = f64> {
+    /// Finds a root of f() starting from a and b.
+    fn solve<F: FnMut(T) -> T>(&self, f: F, a: T, b: T) -> Result<RootReport<T>, NumlError>;
+}
+
+/// Bisection, see bisect().
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bisection<T = f64> {
+    pub tol: T,
+}
+
+impl<T: Real> Default for Bisection<T> {
+    fn default() -> Self {
+        Bisection { tol: T::ZERO }
+    }
+}
+
+impl<T: Real> RootSolver<T> for Bisection<T> {
+    fn solve<F: FnMut(T) -> T>(&self, f: F, a: T, b: T) -> Result<RootReport<T>, NumlError> {
+        bisect(f, a, b, self.tol)
+    }
+}
+
+/// Brent's method, see brent().
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Brent<T = f64> {
+    pub tol: T,
+}
+
+impl<T: Real> Default for Brent<T> {
+    fn default() -> Self {
+        Brent { tol: T::ZERO }
+    }
+}
+
+impl<T: Real> RootSolver<T> for Brent<T> {
+    fn solve<F: FnMut(T) -> T>(&self, f: F, a: T, b: T) -> Result<RootReport<T>, NumlError> {
+        brent(f, a, b, self.tol)
+    }
+}
+
+/// The ITP method, see itp().
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Itp<T = f64> {
+    pub tol: T,
+    pub options: ItpOptions<T>,
+}
+
+impl<T: Real> Default for Itp<T> {
+    fn default() -> Self {
+        Itp { tol: T::ZERO, options: ItpOptions::default() }
+    }
+}
+
+impl<T: Real> RootSolver<T> for Itp<T> {
+    fn solve<F: FnMut(T) -> T>(&self, f: F, a: T, b: T) -> Result<RootReport<T>, NumlError> {
+        itp(f, a, b, self.tol, self.options)
+    }
+}
+
+/// The secant method.
+///
+/// Each iteration replaces the derivative in Newton's method by the slope of the line through the
+/// last two iterates, so that it needs a single new evaluation of f() per iteration instead of
+/// the three that nqn() uses, and converges with order 1.618 near a simple root. Like Newton's
+/// method it does not keep the root bracketed and can diverge from poor starting points.
+///
+/// The iteration stops when f() is exactly zero at an iterate, or when a step is no larger than
+/// tol + 4*EPS*|x|. The default tol is EPS and the default max_iterations is 100.
+///
+/// Errors:
+/// - NumlError::InvalidInput if a or b is not finite, if a == b, or if tol is negative or NaN
+/// - NumlError::NonFiniteValue if f() evaluates to NaN or an infinity
+/// - NumlError::DerivativeZeroError if the last two iterates have equal function values
+/// - NumlError::Divergence if an iterate stops being finite
+/// - NumlError::NoConvergence if no stopping criterion is met within max_iterations, carrying the
+///   secant slope as the derivative
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Secant<T = f64> {
+    pub tol: T,
+    pub max_iterations: usize,
+}
+
+impl<T: Real> Default for Secant<T> {
+    fn default() -> Self {
+        Secant { tol: T::EPSILON, max_iterations: 100 }
+    }
+}
+
+impl<T: Real> RootSolver<T> for Secant<T> {
+    fn solve<F: FnMut(T) -> T>(&self, f: F, a: T, b: T) -> Result<RootReport<T>, NumlError> {
+        check_tol(self.tol)?;
+        if !a.is_finite() {
+            return Err(NumlError::InvalidInput { name: "a", value: a.value() });
+        }
+        if !b.is_finite() || a == b {
+            return Err(NumlError::InvalidInput { name: "b", value: b.value() });
+        }
+
+        let mut f = Counted::new(f);
+        let (mut x0, mut x1) = (a, b);
+        let mut f0 = f.eval(x0);
+        if f0 == T::ZERO {
+            return Ok(RootReport { root: x0, iterations: 0, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+        }
+        let mut f1 = f.eval(x1);
+
+        let mut iterations = 0;
+        loop {
+            if f1 == T::ZERO {
+                return Ok(RootReport { root: x1, iterations, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+            }
+            let slope = (f1 - f0)/(x1 - x0);
+            if iterations >= self.max_iterations {
+                return Err(NumlError::NoConvergence { iterations, x: x1.value(), fx: f1.value(), derivative: slope.value() });
+            }
+            if !f0.is_finite() {
+                return Err(NumlError::NonFiniteValue { x: x0.value(), fx: f0.value(), derivative: f64::NAN });
+            }
+
+            let x2 = newton_step(x1, f1, slope, T::ONE, T::INFINITY)?;
+            if !x2.is_finite() {
+                return Err(NumlError::Divergence { iterations, x: x1.value() });
+            }
+            let f2 = f.eval(x2);
+            iterations += 1;
+
+            if (x2 - x1).abs() <= self.tol + T::from_f64(4.0)*T::EPSILON*x2.abs() {
+                if !f2.is_finite() {
+                    return Err(NumlError::NonFiniteValue { x: x2.value(), fx: f2.value(), derivative: f64::NAN });
+                }
+                let termination = if f2 == T::ZERO { Termination::ExactRoot } else { Termination::StepTolerance };
+                return Ok(RootReport { root: x2, iterations, evaluations: f.evaluations, residual: f2.abs(), termination });
+            }
+
+            (x0, f0) = (x1, f1);
+            (x1, f1) = (x2, f2);
+        }
+    }
+}
+
+/// How a regula falsi method scales the function value at the end of the bracket which has been
+/// kept twice in a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scaling {
+    Illinois,
+    Pegasus,
+    AndersonBjorck,
+}
+
+/// The Illinois variant of regula falsi.
+///
+/// Plain regula falsi keeps one end of the bracket fixed on convex functions and then converges
+/// only linearly. The Illinois method halves the function value at an end which is kept twice in
+/// a row, which restores superlinear convergence. The Pegasus and Anderson-Björck variants use
+/// more elaborate scaling factors and usually converge a little faster.
+///
+/// The root is kept bracketed at all times. The iteration stops when f() is exactly zero at an
+/// evaluated point, when the bracket is no wider than tol, or when its endpoints are adjacent
+/// floating point numbers; the default tol is zero and the default max_iterations is 100. The
+/// returned root is the endpoint of the final bracket with the smaller |f|.
+///
+/// The errors are those of bisect(), plus a NumlError::NoConvergence if no stopping criterion is
+/// met within max_iterations, carrying the slope of the final bracket as the derivative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Illinois<T = f64> {
+    pub tol: T,
+    pub max_iterations: usize,
+}
+
+/// The Pegasus variant of regula falsi, which scales the function value at an end which is kept
+/// twice in a row by f(b)/(f(b) + f(x)), where b is the end being replaced by x. See Illinois.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pegasus<T = f64> {
+    pub tol: T,
+    pub max_iterations: usize,
+}
+
+/// The Anderson-Björck variant of regula falsi, which scales the function value at an end which
+/// is kept twice in a row by 1 - f(x)/f(b), or by 1/2 if that is not positive, where b is the end
+/// being replaced by x. See Illinois.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AndersonBjorck<T = f64> {
+    pub tol: T,
+    pub max_iterations: usize,
+}
+
+macro_rules! impl_regula_falsi {
+    ($($solver:ident => $scaling:expr),*) => {$(
+        impl<T: Real> Default for $solver<T> {
+            fn default() -> Self {
+                $solver { tol: T::ZERO, max_iterations: 100 }
+            }
+        }
+
+        impl<T: Real> RootSolver<T> for $solver<T> {
+            fn solve<F: FnMut(T) -> T>(&self, f: F, a: T, b: T) -> Result<RootReport<T>, NumlError> {
+                regula_falsi(f, a, b, self.tol, self.max_iterations, $scaling)
+            }
+        }
+    )*};
+}
+
+impl_regula_falsi!(Illinois => Scaling::Illinois, Pegasus => Scaling::Pegasus, AndersonBjorck => Scaling::AndersonBjorck);
+
+fn regula_falsi<T: Real>(f: impl FnMut(T) -> T, a: T, b: T, tol: T, max_iterations: usize, scaling: Scaling) -> Result<RootReport<T>, NumlError> {
+    check_tol(tol)?;
+    let mut f = Counted::new(f);
+    // b is always the newest point and the root lies between a and b, which are not ordered.
+    let (mut a, mut fa, mut b, mut fb) = match check_bracket(&mut f, a, b)? {
+        Bracket::Root { x } => {
+            return Ok(RootReport { root: x, iterations: 0, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+        }
+        Bracket::Interval { a, fa, b, fb } => (a, fa, b, fb),
+    };
+
+    let half = T::from_f64(0.5);
+    let mut iterations = 0;
+    loop {
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+        let m = midpoint(low, high);
+        if high - low <= tol || m == low || m == high {
+            let (root, fx) = if fa.abs() <= fb.abs() { (a, fa) } else { (b, fb) };
+            return Ok(RootReport { root, iterations, evaluations: f.evaluations, residual: fx.abs(), termination: Termination::BracketTolerance });
+        }
+        if iterations >= max_iterations {
+            let slope = (fb - fa)/(b - a);
+            return Err(NumlError::NoConvergence { iterations, x: b.value(), fx: fb.value(), derivative: slope.value() });
+        }
+
+        let mut x = b - fb*(b - a)/(fb - fa);
+        // Interpolation can fail to land strictly inside the bracket when an end has an infinite
+        // value or the bracket is only a few ulps wide.
+        if !(x > low && x < high) {
+            x = m;
+        }
+        let fx = f.eval(x);
+        check_value(x, fx)?;
+        iterations += 1;
+        if fx == T::ZERO {
+            return Ok(RootReport { root: x, iterations, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+        }
+
+        if (fx < T::ZERO) != (fb < T::ZERO) {
+            a = b;
+            fa = fb;
+        } else {
+            // a is kept for the second time in a row, so its weight is reduced.
+            let factor = match scaling {
+                Scaling::Illinois => half,
+                Scaling::Pegasus => fb/(fb + fx),
+                Scaling::AndersonBjorck => {
+                    let factor = T::ONE - fx/fb;
+                    if factor > T::ZERO { factor } else { half }
+                }
+            };
+            fa *= factor;
+        }
+        b = x;
+        fb = fx;
+    }
+}
+
+/// Ridders' method.
+///
+/// Each iteration evaluates f() at the midpoint m of the bracket and then at the point given by
+/// fitting an exponential through the function values at a, m and b, which lies inside the
+/// bracket by construction. This converges quadratically per iteration near a simple root, at the
+/// cost of two evaluations per iteration, and is very robust.
+///
+/// The stopping rules, defaults, returned root and errors are the same as for Illinois.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ridders<T = f64> {
+    pub tol: T,
+    pub max_iterations: usize,
+}
+
+impl<T: Real> Default for Ridders<T> {
+    fn default() -> Self {
+        Ridders { tol: T::ZERO, max_iterations: 100 }
+    }
+}
+
+impl<T: Real> RootSolver<T> for Ridders<T> {
+    fn solve<F: FnMut(T) -> T>(&self, f: F, a: T, b: T) -> Result<RootReport<T>, NumlError> {
+        check_tol(self.tol)?;
+        let mut f = Counted::new(f);
+        let (mut a, mut fa, mut b, mut fb) = match check_bracket(&mut f, a, b)? {
+            Bracket::Root { x } => {
+                return Ok(RootReport { root: x, iterations: 0, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+            }
+            Bracket::Interval { a, fa, b, fb } => (a, fa, b, fb),
+        };
+
+        let mut iterations = 0;
+        loop {
+            let m = midpoint(a, b);
+            if b - a <= self.tol || m == a || m == b {
+                let (root, fx) = if fa.abs() <= fb.abs() { (a, fa) } else { (b, fb) };
+                return Ok(RootReport { root, iterations, evaluations: f.evaluations, residual: fx.abs(), termination: Termination::BracketTolerance });
+            }
+            if iterations >= self.max_iterations {
+                let slope = (fb - fa)/(b - a);
+                return Err(NumlError::NoConvergence { iterations, x: m.value(), fx: ((fa + fb)/T::from_f64(2.0)).value(), derivative: slope.value() });
+            }
+
+            let fm = f.eval(m);
+            check_value(m, fm)?;
+            iterations += 1;
+            if fm == T::ZERO {
+                return Ok(RootReport { root: m, iterations, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+            }
+
+            // fa and fb have opposite signs, so the square root is of a positive number. It is
+            // scaled to avoid overflow.
+            let scale = fm.abs().max(fa.abs()).max(fb.abs());
+            let s = ((fm/scale)*(fm/scale) - (fa/scale)*(fb/scale)).sqrt()*scale;
+            let mut x = m + (m - a)*(fa - fb).signum()*fm/s;
+            if !(x > a && x < b) {
+                x = m;
+            }
+            let fx = if x == m { fm } else { f.eval(x) };
+            check_value(x, fx)?;
+            if fx == T::ZERO {
+                return Ok(RootReport { root: x, iterations, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+            }
+
+            // Keep the narrowest of the brackets between neighbouring points among a, m, x and b
+            // which still contain a sign change. There is at least one, since fa and fb have
+            // opposite signs.
+            let (p, fp, q, fq) = if x < m { (x, fx, m, fm) } else { (m, fm, x, fx) };
+            let points = [(a, fa), (p, fp), (q, fq), (b, fb)];
+            let mut narrowest: Option<(T, T, T, T)> = None;
+            for pair in points.windows(2) {
+                let ((low, f_low), (high, f_high)) = (pair[0], pair[1]);
+                let brackets = high > low && (f_low < T::ZERO) != (f_high < T::ZERO);
+                if brackets && narrowest.is_none_or(|(l, _, h, _)| high - low < h - l) {
+                    narrowest = Some((low, f_low, high, f_high));
+                }
+            }
+            if let Some(bracket) = narrowest {
+                (a, fa, b, fb) = bracket;
+            }
+        }
+    }
+}
+
+/// Newton's method safeguarded by bisection, in the style of rtsafe from Numerical Recipes.
+///
+/// The root is kept bracketed at all times. Each iteration computes the derivative at the current
+/// point with derivative_mut(), and takes the Newton step if it lands strictly inside the bracket
+/// and is less than half as long as the step before last; otherwise it bisects the bracket. This
+/// never jumps far away on a near-zero derivative the way nqn() can, and never cycles, while
+/// keeping quadratic convergence near a simple root. Derivatives which come out NaN, infinite or
+/// zero, for example because a difference point fell outside the domain of f(), simply cause a
+/// bisection step.
+///
+/// Every iteration costs three evaluations of f(): one at the new point and two for the
+/// derivative. typ is passed on to derivative_mut(); see NumlError::TypError for details.
+///
+/// The iteration stops when f() is exactly zero at an evaluated point, when a Newton step is no
+/// larger than tol + 4*EPS*|x|, when the bracket is no wider than tol, or when its endpoints are
+/// adjacent floating point numbers. The defaults are tol = 0, typ = 1 and max_iterations = 100.
+///
+/// The errors are those of bisect(), plus NumlError::TypError or NumlError::InvalidInput for an
+/// unusable typ, and a NumlError::NoConvergence if no stopping criterion is met within
+/// max_iterations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafeNewton<T = f64> {
+    pub tol: T,
+    pub typ: T,
+    pub max_iterations: usize,
+}
+
+impl<T: Real> Default for SafeNewton<T> {
+    fn default() -> Self {
+        SafeNewton { tol: T::ZERO, typ: T::ONE, max_iterations: 100 }
+    }
+}
+
+impl<T: Real> RootSolver<T> for SafeNewton<T> {
+    fn solve<F: FnMut(T) -> T>(&self, f: F, a: T, b: T) -> Result<RootReport<T>, NumlError> {
+        check_tol(self.tol)?;
+        check_typ(self.typ)?;
+        let mut f = Counted::new(f);
+        let (a, fa, b, fb) = match check_bracket(&mut f, a, b)? {
+            Bracket::Root { x } => {
+                return Ok(RootReport { root: x, iterations: 0, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+            }
+            Bracket::Interval { a, fa, b, fb } => (a, fa, b, fb),
+        };
+
+        // f(negative) < 0 < f(positive); the two are not ordered.
+        let (mut negative, mut f_negative, mut positive, mut f_positive) = if fa < T::ZERO { (a, fa, b, fb) } else { (b, fb, a, fa) };
+        let mut x = midpoint(a, b);
+        let mut fx = f.eval(x);
+        check_value(x, fx)?;
+        let mut step_before_last = b - a;
+        let mut step = step_before_last;
+
+        let mut iterations = 0;
+        loop {
+            if fx == T::ZERO {
+                return Ok(RootReport { root: x, iterations, evaluations: f.evaluations, residual: T::ZERO, termination: Termination::ExactRoot });
+            }
+            if fx < T::ZERO {
+                (negative, f_negative) = (x, fx);
+            } else {
+                (positive, f_positive) = (x, fx);
+            }
+
+            let (low, high) = if negative < positive { (negative, positive) } else { (positive, negative) };
+            let m = midpoint(low, high);
+            if high - low <= self.tol || m == low || m == high {
+                let (root, residual) = if f_negative.abs() <= f_positive.abs() { (negative, -f_negative) } else { (positive, f_positive) };
+                return Ok(RootReport { root, iterations, evaluations: f.evaluations, residual, termination: Termination::BracketTolerance });
+            }
+
+            let dfx = derivative_mut(|t| f.eval(t), x, self.typ)?;
+            if iterations >= self.max_iterations {
+                return Err(NumlError::NoConvergence { iterations, x: x.value(), fx: fx.value(), derivative: dfx.value() });
+            }
+
+            let newton = x - fx/dfx;
+            let use_newton = dfx.is_finite() && dfx != T::ZERO && newton > low && newton < high
+                && (T::from_f64(2.0)*fx).abs() <= (step_before_last*dfx).abs();
+            step_before_last = step;
+            let next = if use_newton { newton } else { m };
+            step = next - x;
+            x = next;
+            fx = f.eval(x);
+            check_value(x, fx)?;
+            iterations += 1;
+
+            if use_newton && fx != T::ZERO && step.abs() <= self.tol + T::from_f64(4.0)*T::EPSILON*x.abs() {
+                return Ok(RootReport { root: x, iterations, evaluations: f.evaluations, residual: fx.abs(), termination: Termination::StepTolerance });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_cubic(x: f64) -> f64 {
+        (x*x*x) + (2.0*x*x) - 0.4
+    }
+
+    /// Solves sample_cubic with any solver, as calling code written against the trait would.
+    fn solve_cubic(solver: &impl RootSolver) -> RootReport {
+        solver.solve(sample_cubic, 0.0, 1.0).unwrap()
+    }
+
+    #[test]
+    fn test_all_solvers_agree() {
+        let reference = solve_cubic(&Brent::default()).root;
+        let reports = [
+            solve_cubic(&Bisection::default()),
+            solve_cubic(&Itp::default()),
+            solve_cubic(&Secant::default()),
+            solve_cubic(&Illinois::default()),
+            solve_cubic(&Pegasus::default()),
+            solve_cubic(&AndersonBjorck::default()),
+            solve_cubic(&Ridders::default()),
+            solve_cubic(&SafeNewton::default()),
+        ];
+        for report in reports {
+            assert!((report.root - reference).abs() <= 4.0*f64::EPSILON);
+            assert!(report.residual < 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_secant() {
+        let report = Secant::default().solve(sample_cubic, 1.0, 0.9).unwrap();
+        assert!(sample_cubic(report.root).abs() < 1e-15);
+        assert_eq!(report.evaluations, report.iterations + 2);
+        // One evaluation per iteration, against three for nqn().
+        let newton = crate::newton_solve(sample_cubic, 1.0, crate::NewtonOptions { typ: 0.5, ..Default::default() }).unwrap();
+        assert!(report.evaluations < newton.evaluations);
+    }
+
+    #[test]
+    fn test_secant_errors() {
+        let result = Secant::default().solve(|x: f64| x*x + 1.0, -1.0, 1.0);
+        assert!(matches!(result, Err(NumlError::DerivativeZeroError)));
+        let result = Secant::default().solve(sample_cubic, 1.0, 1.0);
+        assert!(matches!(result, Err(NumlError::InvalidInput { name: "b", .. })));
+        let solver = Secant { max_iterations: 3, ..Default::default() };
+        let result = solver.solve(sample_cubic, 10.0, 9.0);
+        assert!(matches!(result, Err(NumlError::NoConvergence { iterations: 3, .. })));
+    }
+
+    #[test]
+    fn test_regula_falsi_variants() {
+        // Plain regula falsi would keep the right end fixed on this convex function and need
+        // hundreds of iterations.
+        let f = |x: f64| x.exp() - 2.0;
+        let illinois = Illinois::default().solve(f, 0.0, 4.0).unwrap();
+        let pegasus = Pegasus::default().solve(f, 0.0, 4.0).unwrap();
+        let anderson_bjorck = AndersonBjorck::default().solve(f, 0.0, 4.0).unwrap();
+        let bisection = Bisection::default().solve(f, 0.0, 4.0).unwrap();
+        for report in [illinois, pegasus, anderson_bjorck] {
+            assert!((report.root - f64::ln(2.0)).abs() <= 2.0*f64::EPSILON);
+            assert!(report.evaluations < bisection.evaluations/2);
+        }
+    }
+
+    #[test]
+    fn test_ridders() {
+        let report = Ridders::default().solve(|x: f64| x.exp() - 2.0, 4.0, 0.0).unwrap();
+        assert!((report.root - f64::ln(2.0)).abs() <= 2.0*f64::EPSILON);
+        assert!(report.evaluations < 30);
+        let report = Ridders::<f32>::default().solve(|x| x*x - 2.0, 0.0, 2.0).unwrap();
+        assert!((report.root - f32::sqrt(2.0)).abs() <= 2.0*f32::EPSILON);
+    }
+
+    #[test]
+    fn test_bracketing_errors_and_limits() {
+        assert!(matches!(Illinois::default().solve(sample_cubic, 1.0, 2.0), Err(NumlError::InvalidBracket { .. })));
+        assert!(matches!(Ridders::default().solve(sample_cubic, 1.0, 2.0), Err(NumlError::InvalidBracket { .. })));
+        let solver = Pegasus { tol: 0.0, max_iterations: 2 };
+        assert!(matches!(solver.solve(sample_cubic, 0.0, 1.0), Err(NumlError::NoConvergence { iterations: 2, .. })));
+        // A discontinuity defeats interpolation, but the bracket still collapses onto it.
+        let step = |x: f64| if x < 0.3 { -1.0 } else { 1.0 };
+        for report in [Illinois::default().solve(step, 0.0, 1.0), Ridders::default().solve(step, 0.0, 1.0)] {
+            assert!((report.unwrap().root - 0.3).abs() <= f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_safe_newton() {
+        let report = SafeNewton { typ: 0.5, ..Default::default() }.solve(sample_cubic, 0.0, 1.0).unwrap();
+        assert!(sample_cubic(report.root).abs() < 1e-15);
+        let bisection = Bisection::default().solve(sample_cubic, 0.0, 1.0).unwrap();
+        assert!(report.evaluations < bisection.evaluations/2);
+    }
+
+    #[test]
+    fn test_safe_newton_where_newton_fails() {
+        // Newton's method started from 0 cycles between 0 and 1 on this cubic.
+        let f = |x: f64| x*x*x - 2.0*x + 2.0;
+        let options = crate::NewtonOptions { max_iterations: 50, ..Default::default() };
+        assert!(matches!(crate::newton_solve(f, 0.0, options), Err(NumlError::NoConvergence { .. })));
+        let report = SafeNewton::default().solve(f, -3.0, 0.0).unwrap();
+        assert!(f(report.root).abs() < 1e-14);
+
+        // The derivative vanishes at the first midpoint, 0, from which nqn() jumps millions away.
+        let g = |x: f64| x*x*x - 0.001;
+        assert!(crate::nqn(g, 0.0, 1.0).unwrap().abs() > 1e6);
+        let report = SafeNewton::default().solve(g, -1.0, 1.0).unwrap();
+        assert!((report.root - 0.1).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_safe_newton_domain_edge() {
+        // Difference points outside the domain only cause bisection steps.
+        let report = SafeNewton::default().solve(|x: f64| x.sqrt() - 0.001, 0.0, 1.0).unwrap();
+        assert!((report.root - 1e-6).abs() < 1e-18);
+    }
+
+    #[test]
+    fn test_safe_newton_errors() {
+        let solver = SafeNewton { typ: 0.0, ..Default::default() };
+        assert!(matches!(solver.solve(sample_cubic, 0.0, 1.0), Err(NumlError::TypError)));
+        assert!(matches!(SafeNewton::default().solve(sample_cubic, 1.0, 2.0), Err(NumlError::InvalidBracket { .. })));
+        let solver = SafeNewton { max_iterations: 1, ..Default::default() };
+        assert!(matches!(solver.solve(sample_cubic, 0.0, 1.0), Err(NumlError::NoConvergence { iterations: 1, .. })));
+    }
+}